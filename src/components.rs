@@ -1,5 +1,6 @@
 pub use crate::prelude::*;
 
+use serde::Deserialize;
 use std::collections::HashSet;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -17,6 +18,69 @@ pub struct Player {
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Enemy;
 
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub enum SpellKind {
+    // a ranged attack using the caster's own damage
+    Bolt,
+    HealAlly,
+    // summons power copies of the named template
+    Summon(String)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spell {
+    pub kind: SpellKind,
+    pub range: i32,
+    pub power: i32,
+    pub cooldown: i32,
+    // turns until the spell can be cast again
+    pub ready_in: i32
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spellcaster {
+    pub spells: Vec<Spell>,
+    pub keep_distance: i32
+}
+
+// what a boss does when it is worn down to a new phase
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub enum PhaseAction {
+    // spawns count copies of the named template around the boss
+    Summon(String, i32),
+    // adds to the boss's damage for the rest of the fight
+    Enrage(i32),
+    Heal(i32)
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct BossPhase {
+    // the phase starts once health falls to this fraction of max
+    pub below: f32,
+    pub action: PhaseAction,
+    pub message: String
+}
+
+// a unique monster, holding the phases it has yet to enter
+#[derive(Clone, Debug, PartialEq)]
+pub struct Boss {
+    pub phases: Vec<BossPhase>
+}
+
+// monsters spawned together share an id, and what they know about the player
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pack(pub usize);
+
+// called up by a spellcaster, worth no experience and dropping nothing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summoned {
+    pub summoner: Entity
+}
+
+// which side an entity fights on, see resources/factions.ron
+#[derive(Clone, Debug, PartialEq)]
+pub struct Faction(pub String);
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Item;
 
@@ -29,23 +93,151 @@ pub struct MovingRandomly;
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ChasingPlayer;
 
+// where a chasing monster last saw the player, and how long ago
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct ProvidesHealing {
-    pub amount: i32
+pub struct PlayerMemory {
+    pub last_seen: Point,
+    pub turns_since_seen: i32
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Asleep;
+
+// a confused monster staggers about at random instead of following its ai
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Confused {
+    pub turns: i32
 }
 
+// extra distance over which a sleeping monster can hear a noise
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Perception(pub i32);
+
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct ProvidesDungeonMap;
+pub struct Stationary;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cowardly;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FleesWhenWounded {
+    // fraction of max health below which the monster runs away
+    pub threshold: f32
+}
+
+impl FleesWhenWounded {
+    pub fn is_wounded(&self, health: &Health) -> bool {
+        (health.current as f32) < health.max as f32 * self.threshold
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Patrolling {
+    pub direction: Point
+}
+
+// the effects an item has when it is used, in the order the template lists them
+#[derive(Clone, Debug, PartialEq)]
+pub struct Provides(pub Vec<Effect>);
+
+// items with a range are aimed at a tile before they are used
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ranged(pub i32);
+
+// uses left in a wand, which is kept rather than used up
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Charges(pub i32);
+
+// an item seen by magic, drawn even when out of sight
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Revealed;
 
 #[derive(Clone, PartialEq)]
 pub struct Carried(pub Entity);
 
+// a pile of coins, collected into the wallet as soon as it is walked over
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gold(pub i32);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wallet(pub i32);
+
+// sells the items in its shop, which cannot be picked up until paid for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shopkeeper;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ForSale {
+    pub price: i32,
+    pub shopkeeper: Entity
+}
+
+// identical stackable items merge into one entity when picked up
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stackable;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quantity(pub i32);
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Damage(pub i32);
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Weapon;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum EquipmentSlot {
+    MainHand,
+    OffHand,
+    Body,
+    Head,
+    Ring,
+    Amulet
+}
+
+impl EquipmentSlot {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            EquipmentSlot::MainHand => "main hand",
+            EquipmentSlot::OffHand => "off hand",
+            EquipmentSlot::Body => "body",
+            EquipmentSlot::Head => "head",
+            EquipmentSlot::Ring => "ring",
+            EquipmentSlot::Amulet => "amulet"
+        }
+    }
+}
+
+// an item that can be worn or wielded in the given slot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Equippable(pub EquipmentSlot);
+
+// added to an item's damage, negative for cursed items
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Enchantment(pub i32);
+
+// cursed equipment cannot be taken off or dropped once it is equipped
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cursed;
+
+// the item's enchantment is known, from wearing it or identifying it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Appraised;
+
+// a carried item that is in use, only these count towards the owner's stats
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Equipped {
+    pub owner: Entity,
+    pub slot: EquipmentSlot
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GrantsExperience(pub i32);
+
+// name of the template whose loot table is rolled on death
+#[derive(Clone, Debug, PartialEq)]
+pub struct DropsLoot(pub String);
+
 // state
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Health {
@@ -56,6 +248,127 @@ pub struct Health {
 #[derive(Clone, PartialEq)]
 pub struct Name(pub String);
 
+// flavour text shown when an item is inspected
+#[derive(Clone, PartialEq)]
+pub struct Description(pub String);
+
+// character level, separate from the dungeon depth held on Player
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Experience {
+    pub level: i32,
+    pub current: i32,
+    pub next_level: i32
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Self {
+            level: 1,
+            current: 0,
+            next_level: Self::needed_for(1)
+        }
+    }
+}
+
+impl Experience {
+    // experience required to advance from the given level to the next
+    pub fn needed_for(level: i32) -> i32 {
+        level * 10
+    }
+}
+
+// recovers a point of health every so many turns
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Regeneration {
+    pub interval: i32,
+    pub timer: i32
+}
+
+impl Regeneration {
+    pub fn new(interval: i32) -> Self {
+        Self {
+            interval,
+            timer: 0
+        }
+    }
+}
+
+// lights up the tiles around it, or around whoever has it equipped
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightSource {
+    pub radius: i32,
+    pub colour: RGB
+}
+
+// the player passes turns until healed or something comes into view
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Resting;
+
+// how well fed the player is, it drops by one every turn and food tops it up
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hunger {
+    pub food: i32
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HungerState {
+    Satiated,
+    Hungry,
+    Weak,
+    Starving
+}
+
+impl Hunger {
+    pub const START: i32 = 600;
+    pub const MAX: i32 = 1000;
+
+    pub fn state(&self) -> HungerState {
+        match self.food {
+            f if f > 300 => HungerState::Satiated,
+            f if f > 100 => HungerState::Hungry,
+            f if f > 0 => HungerState::Weak,
+            _ => HungerState::Starving
+        }
+    }
+}
+
+impl HungerState {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            HungerState::Satiated => "Satiated",
+            HungerState::Hungry => "Hungry",
+            HungerState::Weak => "Weak",
+            HungerState::Starving => "Starving"
+        }
+    }
+
+    pub fn colour(&self) -> (u8, u8, u8) {
+        match self {
+            HungerState::Satiated => GREEN,
+            HungerState::Hungry => YELLOW,
+            HungerState::Weak => ORANGE,
+            HungerState::Starving => RED
+        }
+    }
+
+    // how many times slower health comes back, none at all when too hungry
+    pub fn regen_multiplier(&self) -> Option<i32> {
+        match self {
+            HungerState::Satiated => Some(1),
+            HungerState::Hungry => Some(2),
+            _ => None
+        }
+    }
+
+    // weakness from hunger takes the edge off every blow
+    pub fn damage_penalty(&self) -> i32 {
+        match self {
+            HungerState::Weak | HungerState::Starving => 1,
+            _ => 0
+        }
+    }
+}
+
 // messages
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct WantsToMove {
@@ -69,9 +382,51 @@ pub struct WantsToAttack {
     pub target: Entity
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LevelUp {
+    pub level: i32
+}
+
+// a sound made at pos, heard by sleeping monsters within volume plus
+// their perception in walking distance
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Noise {
+    pub pos: Point,
+    pub volume: i32
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ActivateItem {
     pub used_by: Entity,
+    pub item: Entity,
+    // the tile a ranged item is aimed at
+    pub target: Option<Point>
+}
+
+// damage that does not come from the source's weapons, such as spells
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InflictDamage {
+    pub source: Entity,
+    pub target: Entity,
+    pub amount: i32
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToDrop {
+    pub entity: Entity,
+    pub item: Entity
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToBuy {
+    pub buyer: Entity,
+    pub item: Entity
+}
+
+// equips the item, or puts it back in the backpack if it is already equipped
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToEquip {
+    pub entity: Entity,
     pub item: Entity
 }
 
@@ -0,0 +1,355 @@
+use crate::prelude::*;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+const FIREBALL_VOLUME: i32 = 8;
+
+// every effect an item can provide, named in the template file. an effect
+// that is not listed here fails to load rather than being ignored
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+pub enum Effect {
+    Healing(i32),
+    MagicMap,
+    // identifies everything the user is carrying
+    Identify,
+    // moves the user to a random spot on the level
+    Teleport,
+    Fireball { damage: i32, radius: i32 },
+    Confusion(i32),
+    // shows where every item on the level lies
+    RevealItems,
+    // staves off hunger by this many turns
+    Food(i32),
+    // lifts the curse from everything the user is carrying
+    RemoveCurse,
+    // wins a monster over to the user's side for good
+    Charm,
+}
+
+// what an effect works with while its item is being used
+pub struct EffectContext<'a> {
+    pub user: Entity,
+    pub target: Option<Point>,
+    pub commands: &'a mut CommandBuffer,
+    pub map: &'a mut Map,
+    pub log: &'a mut GameLog,
+    pub identification: &'a mut Identification,
+    pub rng: &'a mut RandomNumberGenerator,
+    // healing is applied once every item has been used
+    pub healing: &'a mut Vec<(Entity, i32)>,
+}
+
+// everything one kind of effect does, its description, aimed area and result
+// kept side by side
+trait EffectHandler {
+    // shown when inspecting an identified item
+    fn describe(&self) -> String;
+
+    // the area shown while aiming the item
+    fn radius(&self) -> i32 {
+        0
+    }
+
+    fn apply(&self, ecs: &SubWorld, ctx: &mut EffectContext);
+}
+
+impl Effect {
+    // the registry of effects, adding one means a variant above, a line here
+    // and its handler below
+    fn handler(&self) -> Box<dyn EffectHandler> {
+        match *self {
+            Effect::Healing(amount) => Box::new(Healing(amount)),
+            Effect::MagicMap => Box::new(MagicMap),
+            Effect::Identify => Box::new(Identify),
+            Effect::Teleport => Box::new(Teleport),
+            Effect::Fireball { damage, radius } => Box::new(Fireball { damage, radius }),
+            Effect::Confusion(turns) => Box::new(Confusion(turns)),
+            Effect::RevealItems => Box::new(RevealItems),
+            Effect::Food(amount) => Box::new(Food(amount)),
+            Effect::RemoveCurse => Box::new(RemoveCurse),
+            Effect::Charm => Box::new(Charm),
+        }
+    }
+
+    pub fn radius(&self) -> i32 {
+        self.handler().radius()
+    }
+
+    pub fn describe(&self) -> String {
+        self.handler().describe()
+    }
+
+    pub fn apply(&self, ecs: &SubWorld, ctx: &mut EffectContext) {
+        self.handler().apply(ecs, ctx)
+    }
+}
+
+struct Healing(i32);
+
+impl EffectHandler for Healing {
+    fn describe(&self) -> String {
+        format!("Heals {} health.", self.0)
+    }
+
+    fn apply(&self, _ecs: &SubWorld, ctx: &mut EffectContext) {
+        ctx.healing.push((ctx.user, self.0));
+    }
+}
+
+struct MagicMap;
+
+impl EffectHandler for MagicMap {
+    fn describe(&self) -> String {
+        "Reveals the layout of the level.".to_string()
+    }
+
+    fn apply(&self, _ecs: &SubWorld, ctx: &mut EffectContext) {
+        ctx.map.revealed_tiles.iter_mut().for_each(|t| *t = true);
+        ctx.log.add(LogKind::Item, "The layout of the level is revealed to you.");
+    }
+}
+
+struct Identify;
+
+impl EffectHandler for Identify {
+    fn describe(&self) -> String {
+        "Identifies everything you carry.".to_string()
+    }
+
+    fn apply(&self, ecs: &SubWorld, ctx: &mut EffectContext) {
+        <(Entity, &Carried, &Name)>::query()
+            .iter(ecs)
+            .filter(|(_, carried, _)| carried.0 == ctx.user)
+            .for_each(|(item, _, name)| {
+                let appearance = ctx.identification.name_of(&name.0);
+                if ctx.identification.identify(&name.0) {
+                    ctx.log.add(LogKind::Item, format!("The {} is a {}.", appearance, name.0));
+                }
+
+                // equipment also gives up its enchantment
+                if let Ok(entry) = ecs.entry_ref(*item)
+                    && entry.get_component::<Equippable>().is_ok()
+                    && entry.get_component::<Appraised>().is_err()
+                {
+                    ctx.commands.add_component(*item, Appraised);
+                    if let Ok(enchantment) = entry.get_component::<Enchantment>() {
+                        ctx.log.add(LogKind::Item, format!("The {} is {:+}.", name.0, enchantment.0));
+                    }
+                }
+            });
+    }
+}
+
+struct Teleport;
+
+impl EffectHandler for Teleport {
+    fn describe(&self) -> String {
+        "Moves you somewhere else on the level.".to_string()
+    }
+
+    fn apply(&self, ecs: &SubWorld, ctx: &mut EffectContext) {
+        let occupied: HashSet<Point> = <&Point>::query()
+            .filter(component::<Health>())
+            .iter(ecs)
+            .copied()
+            .collect();
+        let floor: Vec<Point> = ctx.map.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| **tile == TileType::Floor)
+            .map(|(idx, _)| ctx.map.index_to_point2d(idx))
+            .filter(|pt| !occupied.contains(pt))
+            .collect();
+        if let Some(destination) = ctx.rng.random_slice_entry(&floor) {
+            ctx.commands.push(((), WantsToMove {
+                entity: ctx.user,
+                destination: *destination
+            }));
+            ctx.log.add(LogKind::Item, "The world spins, and you find yourself somewhere else.");
+        }
+    }
+}
+
+struct Fireball {
+    damage: i32,
+    radius: i32
+}
+
+impl EffectHandler for Fireball {
+    fn describe(&self) -> String {
+        format!("Deals {} damage to everything within {} tiles.", self.damage, self.radius)
+    }
+
+    fn radius(&self) -> i32 {
+        self.radius
+    }
+
+    fn apply(&self, ecs: &SubWorld, ctx: &mut EffectContext) {
+        let target = match ctx.target {
+            Some(target) => target,
+            None => return
+        };
+
+        // everything caught in the blast is hurt, including the user, though
+        // shopkeepers are well protected against their customers
+        ctx.log.add(LogKind::Attack, "A ball of fire bursts into flame!");
+        <(Entity, &Point)>::query()
+            .filter(component::<Health>() & !component::<Shopkeeper>())
+            .iter(ecs)
+            .filter(|(_, pos)| DistanceAlg::Pythagoras.distance2d(target, **pos) <= self.radius as f32)
+            .for_each(|(victim, _)| {
+                ctx.commands.push(((), InflictDamage {
+                    source: ctx.user,
+                    target: *victim,
+                    amount: self.damage
+                }));
+            });
+        ctx.commands.push(((), Noise {
+            pos: target,
+            volume: FIREBALL_VOLUME
+        }));
+    }
+}
+
+struct Confusion(i32);
+
+impl EffectHandler for Confusion {
+    fn describe(&self) -> String {
+        format!("Confuses a monster for {} turns.", self.0)
+    }
+
+    fn apply(&self, ecs: &SubWorld, ctx: &mut EffectContext) {
+        let target = match ctx.target {
+            Some(target) => target,
+            None => return
+        };
+
+        let victims: Vec<(Entity, String)> = <(Entity, &Point, &Name)>::query()
+            .filter(component::<Enemy>())
+            .iter(ecs)
+            .filter(|(_, pos, _)| **pos == target)
+            .map(|(victim, _, name)| (*victim, name.0.clone()))
+            .collect();
+        if victims.is_empty() {
+            ctx.log.add(LogKind::Item, "Nothing happens.");
+        }
+        victims.iter().for_each(|(victim, name)| {
+            ctx.commands.add_component(*victim, Confused { turns: self.0 });
+            ctx.log.add(LogKind::Item, format!("The {} looks confused.", name));
+        });
+    }
+}
+
+struct RevealItems;
+
+impl EffectHandler for RevealItems {
+    fn describe(&self) -> String {
+        "Shows where the items on the level lie.".to_string()
+    }
+
+    fn apply(&self, ecs: &SubWorld, ctx: &mut EffectContext) {
+        let mut count = 0;
+        <(Entity, &Point)>::query()
+            .filter(component::<Item>())
+            .iter(ecs)
+            .for_each(|(found, pos)| {
+                ctx.commands.add_component(*found, Revealed{});
+                ctx.map.revealed_tiles[map_idx(pos.x, pos.y)] = true;
+                count += 1;
+            });
+        ctx.log.add(LogKind::Item, format!("You sense {} items on this level.", count));
+    }
+}
+
+struct Food(i32);
+
+impl EffectHandler for Food {
+    fn describe(&self) -> String {
+        format!("Keeps hunger at bay for {} turns.", self.0)
+    }
+
+    fn apply(&self, ecs: &SubWorld, ctx: &mut EffectContext) {
+        let hunger = ecs
+            .entry_ref(ctx.user)
+            .ok()
+            .and_then(|e| e.get_component::<Hunger>().ok().copied());
+
+        if let Some(hunger) = hunger {
+            // eating while starving only makes up for what is owed from zero
+            let food = i32::min(Hunger::MAX, i32::max(hunger.food, 0) + self.0);
+            ctx.commands.add_component(ctx.user, Hunger { food });
+            if food >= Hunger::MAX {
+                ctx.log.add(LogKind::Item, "You are completely full.");
+            } else {
+                ctx.log.add(LogKind::Item, "That hit the spot.");
+            }
+        }
+    }
+}
+
+struct RemoveCurse;
+
+impl EffectHandler for RemoveCurse {
+    fn describe(&self) -> String {
+        "Lifts the curse from everything you carry.".to_string()
+    }
+
+    fn apply(&self, ecs: &SubWorld, ctx: &mut EffectContext) {
+        ctx.log.add(LogKind::Item, "You feel as if someone is watching over you.");
+        <(Entity, &Carried, &Name)>::query()
+            .filter(component::<Cursed>())
+            .iter(ecs)
+            .filter(|(_, carried, _)| carried.0 == ctx.user)
+            .for_each(|(item, _, name)| {
+                ctx.commands.remove_component::<Cursed>(*item);
+                ctx.log.add(LogKind::Item, format!("The {} is no longer cursed.", name.0));
+            });
+    }
+}
+
+struct Charm;
+
+impl EffectHandler for Charm {
+    fn describe(&self) -> String {
+        "Turns a monster into your ally.".to_string()
+    }
+
+    fn apply(&self, ecs: &SubWorld, ctx: &mut EffectContext) {
+        let target = match ctx.target {
+            Some(target) => target,
+            None => return
+        };
+
+        // the charmed monster joins whichever side the user is on
+        let faction = ecs
+            .entry_ref(ctx.user)
+            .ok()
+            .and_then(|e| e.get_component::<Faction>().ok().cloned())
+            .unwrap_or_else(|| Faction("Player".to_string()));
+
+        let victims: Vec<(Entity, String)> = <(Entity, &Point, &Name)>::query()
+            .filter(component::<Enemy>())
+            .iter(ecs)
+            .filter(|(_, pos, _)| **pos == target)
+            .map(|(victim, _, name)| (*victim, name.0.clone()))
+            .collect();
+        if victims.is_empty() {
+            ctx.log.add(LogKind::Item, "Nothing happens.");
+        }
+        victims.iter().for_each(|(victim, name)| {
+            // bosses answer to nobody
+            let is_boss = ecs
+                .entry_ref(*victim)
+                .is_ok_and(|e| e.get_component::<Boss>().is_ok());
+            if is_boss {
+                ctx.log.add(LogKind::Item, format!("The {} shrugs off the charm.", name));
+                return;
+            }
+            ctx.commands.add_component(*victim, faction.clone());
+            // it no longer hunts with its old pack or remembers the player as prey
+            ctx.commands.remove_component::<Pack>(*victim);
+            ctx.commands.remove_component::<PlayerMemory>(*victim);
+            ctx.log.add(LogKind::Item, format!("The {} is now on your side.", name));
+        });
+    }
+}
@@ -0,0 +1,95 @@
+use crate::prelude::*;
+
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FlowField {
+    TowardPlayer,
+    AwayFromPlayer,
+    TowardItems
+}
+
+// flow fields shared by the monster AI systems. They are only rebuilt once the
+// player or the items have moved, rather than once per monster turn. Tiles never
+// change during play, and a fresh set is made for each new level
+#[derive(Default)]
+pub struct FlowFields {
+    player_pos: Option<Point>,
+    items: Vec<Point>,
+    fields: HashMap<FlowField, DijkstraMap>
+}
+
+impl FlowFields {
+    // looks up where the items lie, once a turn before any monster heads for them
+    pub fn update_items<W: EntityStore>(&mut self, ecs: &W) {
+        let mut items: Vec<Point> = <&Point>::query()
+            .filter(component::<Item>())
+            .iter(ecs)
+            .copied()
+            .collect();
+        items.sort_by_key(|pt| (pt.x, pt.y));
+        if self.items != items {
+            self.fields.remove(&FlowField::TowardItems);
+            self.items = items;
+        }
+    }
+
+    // where the items were at the last update_items
+    pub fn items(&self) -> &[Point] {
+        &self.items
+    }
+
+    // build the field if the cached copy is missing or out of date
+    pub fn ensure<W: EntityStore>(&mut self, field: FlowField, map: &Map, ecs: &W) {
+        let player_pos = <&Point>::query()
+            .filter(component::<Player>())
+            .iter(ecs)
+            .next()
+            .copied();
+        if self.player_pos != player_pos {
+            self.fields.retain(|f, _| *f == FlowField::TowardItems);
+            self.player_pos = player_pos;
+        }
+
+        if self.fields.contains_key(&field) {
+            return;
+        }
+
+        let dijkstra_map = match field {
+            FlowField::TowardPlayer => {
+                let starts: Vec<usize> = player_pos.iter().map(|pt| map.point2d_to_index(*pt)).collect();
+                map.dijkstra_from(&starts)
+            }
+            FlowField::AwayFromPlayer => {
+                self.ensure(FlowField::TowardPlayer, map, ecs);
+                safety_map(&self.fields[&FlowField::TowardPlayer], map)
+            }
+            FlowField::TowardItems => {
+                let starts: Vec<usize> = self.items.iter().map(|pt| map.point2d_to_index(*pt)).collect();
+                map.dijkstra_from(&starts)
+            }
+        };
+        self.fields.insert(field, dijkstra_map);
+    }
+
+    // panics unless the field was built with ensure first
+    pub fn get(&self, field: FlowField) -> &DijkstraMap {
+        &self.fields[&field]
+    }
+}
+
+// inverts the distance to the player, scaled so that monsters prefer running
+// somewhere open rather than just directly away into a dead end
+fn safety_map(chase_map: &DijkstraMap, map: &Map) -> DijkstraMap {
+    let starts: Vec<(usize, f32)> = chase_map.map
+        .iter()
+        .enumerate()
+        .filter(|(_, distance)| **distance < f32::MAX)
+        .map(|(idx, distance)| (idx, *distance * -1.2))
+        .collect();
+
+    let mut flee_map = DijkstraMap::new_empty(SCREEN_WIDTH, SCREEN_HEIGHT, 1024.0);
+    starts.iter().for_each(|(idx, distance)| flee_map.map[*idx] = *distance);
+    DijkstraMap::build_weighted(&mut flee_map, &starts, map);
+    flee_map
+}
@@ -0,0 +1,53 @@
+use crate::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogKind {
+    Info,
+    Attack,
+    Hurt,
+    Kill,
+    Item,
+    Level
+}
+
+impl LogKind {
+    pub fn colour(&self) -> (u8, u8, u8) {
+        match self {
+            LogKind::Info => WHITE,
+            LogKind::Attack => ORANGE,
+            LogKind::Hurt => RED,
+            LogKind::Kill => GOLD,
+            LogKind::Item => CYAN,
+            LogKind::Level => MAGENTA
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogEntry {
+    pub kind: LogKind,
+    pub text: String
+}
+
+// record of everything that happened this game, kept across levels
+#[derive(Clone, Debug, Default)]
+pub struct GameLog {
+    pub entries: Vec<LogEntry>,
+    // how many entries back from the newest the history screen is scrolled
+    pub history_offset: usize
+}
+
+impl GameLog {
+    pub fn add<S: ToString>(&mut self, kind: LogKind, text: S) {
+        self.entries.push(LogEntry {
+            kind,
+            text: text.to_string()
+        });
+    }
+
+    // the newest n entries, oldest first
+    pub fn recent(&self, n: usize) -> &[LogEntry] {
+        let start = self.entries.len().saturating_sub(n);
+        &self.entries[start..]
+    }
+}
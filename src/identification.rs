@@ -0,0 +1,75 @@
+use crate::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+// which items the player has learned this run, and how unknown items look
+#[derive(Clone, Debug, Default)]
+pub struct Identification {
+    // true item name to the appearance it is given for this run
+    appearances: HashMap<String, String>,
+    known: HashSet<String>
+}
+
+impl Identification {
+    // deals each categorised item a random appearance from its category's pool
+    pub fn new(templates: &Templates, rng: &mut RandomNumberGenerator) -> Self {
+        let mut appearances = HashMap::new();
+        templates.appearances.iter().for_each(|pool| {
+            let mut names = pool.names.clone();
+            templates.entities
+                .iter()
+                .filter(|t| t.category == Some(pool.category))
+                .for_each(|t| {
+                    // the pools are checked to be big enough when templates load
+                    let i = rng.random_slice_index(&names).unwrap();
+                    appearances.insert(t.name.clone(), names.remove(i));
+                });
+        });
+
+        Self {
+            appearances,
+            known: HashSet::new()
+        }
+    }
+
+    pub fn is_known(&self, name: &str) -> bool {
+        !self.appearances.contains_key(name) || self.known.contains(name)
+    }
+
+    // the name the player sees for an item
+    pub fn name_of(&self, name: &str) -> String {
+        match self.appearances.get(name) {
+            Some(appearance) if !self.known.contains(name) => appearance.clone(),
+            _ => name.to_string()
+        }
+    }
+
+    // returns true if the item was not already known
+    pub fn identify(&mut self, name: &str) -> bool {
+        !self.is_known(name) && self.known.insert(name.to_string())
+    }
+}
+
+// the name an item is shown with, its enchantment included once that is known
+pub fn item_name<W: EntityStore>(ecs: &W, item: Entity, identification: &Identification) -> String {
+    let entry = match ecs.entry_ref(item) {
+        Ok(entry) => entry,
+        Err(_) => return String::new()
+    };
+    let name = entry
+        .get_component::<Name>()
+        .map(|name| identification.name_of(&name.0))
+        .unwrap_or_default();
+
+    if entry.get_component::<Appraised>().is_err() {
+        return name;
+    }
+    let name = match entry.get_component::<Enchantment>() {
+        Ok(enchantment) => format!("{:+} {}", enchantment.0, name),
+        Err(_) => name
+    };
+    if entry.get_component::<Cursed>().is_ok() {
+        format!("{} (cursed)", name)
+    } else {
+        name
+    }
+}
@@ -0,0 +1,56 @@
+use crate::prelude::*;
+
+// the most items the player can carry at once
+pub const INVENTORY_CAPACITY: usize = 20;
+
+// cursor state for the inventory screen, kept while it is open
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InventoryMenu {
+    pub selected: usize,
+    // index of the first item shown, for inventories longer than the screen
+    pub offset: usize,
+    pub inspecting: bool
+}
+
+impl InventoryMenu {
+    pub fn select(&mut self, selected: usize, count: usize, lines: usize) {
+        self.selected = selected.min(count.saturating_sub(1));
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + lines {
+            self.offset = self.selected + 1 - lines;
+        }
+    }
+}
+
+// items carried by the owner with the name to show for them, sorted so the
+// order does not shift as items change components
+pub fn carried_items<W: EntityStore>(
+    ecs: &W,
+    owner: Entity,
+    identification: &Identification
+) -> Vec<(Entity, String)> {
+    let mut items: Vec<(Entity, String)> = <(Entity, &Item, &Carried, &Name, Option<&Quantity>, Option<&Charges>)>::query()
+        .iter(ecs)
+        .filter(|(_, _, carried, _, _, _)| carried.0 == owner)
+        .map(|(entity, _, _, _, quantity, charges)| {
+            let name = item_name(ecs, *entity, identification);
+            match (quantity, charges) {
+                (Some(quantity), _) if quantity.0 > 1 => (*entity, format!("{} (x{})", name, quantity.0)),
+                (_, Some(Charges(1))) => (*entity, format!("{} (1 charge)", name)),
+                (_, Some(charges)) => (*entity, format!("{} ({} charges)", name, charges.0)),
+                _ => (*entity, name)
+            }
+        })
+        .collect();
+    items.sort_by(|a, b| a.1.cmp(&b.1));
+    items
+}
+
+// a wand with nothing left in it, which is not worth aiming
+pub fn out_of_charges<W: EntityStore>(ecs: &W, item: Entity) -> bool {
+    ecs.entry_ref(item)
+        .ok()
+        .and_then(|entry| entry.get_component::<Charges>().ok().copied())
+        .is_some_and(|charges| charges.0 < 1)
+}
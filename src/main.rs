@@ -1,8 +1,15 @@
 mod camera;
 mod components;
+mod effects;
+mod flow_fields;
+mod game_log;
+mod identification;
+mod inventory;
 mod map;
 mod map_builder;
+mod shop;
 mod spawner;
+mod targeting;
 mod systems;
 mod turn_state;
 
@@ -19,9 +26,16 @@ mod prelude {
     // use crate::mod pulls a module defined at root scope
     pub use crate::camera::*;
     pub use crate::components::*;
+    pub use crate::effects::*;
+    pub use crate::flow_fields::*;
+    pub use crate::game_log::*;
+    pub use crate::identification::*;
+    pub use crate::inventory::*;
     pub use crate::map::*;
     pub use crate::map_builder::*;
+    pub use crate::shop::*;
     pub use crate::spawner::*;
+    pub use crate::targeting::*;
     pub use crate::systems::*;
     pub use crate::turn_state::*;
 }
@@ -29,10 +43,14 @@ mod prelude {
 use std::collections::HashSet;
 use prelude::*;
 
+// one level in this many has a shop, apart from the final one
+const SHOP_CHANCE: i32 = 5;
+
 struct State {
     ecs: World,
     resources: Resources,
     input_systems: Schedule,
+    targeting_systems: Schedule,
     player_systems: Schedule,
     monster_systems: Schedule,
 }
@@ -43,6 +61,7 @@ impl State {
             ecs: World::default(),
             resources: Resources::default(),
             input_systems: build_input_scheduler(),
+            targeting_systems: build_targeting_scheduler(),
             player_systems: build_player_scheduler(),
             monster_systems: build_monster_scheduler(),
         };
@@ -97,6 +116,295 @@ impl State {
         }
     }
 
+    fn show_log(&mut self, ctx: &mut BTerm) {
+        ctx.set_active_console(2);
+        let mut log = self.resources.get_mut::<GameLog>().unwrap();
+
+        const LINES: usize = (SCREEN_HEIGHT * 2 - 6) as usize;
+        let max_offset = log.entries.len().saturating_sub(LINES);
+        match ctx.key {
+            Some(VirtualKeyCode::Up) => log.history_offset += 1,
+            Some(VirtualKeyCode::Down) => log.history_offset = log.history_offset.saturating_sub(1),
+            Some(VirtualKeyCode::PageUp) => log.history_offset += LINES,
+            Some(VirtualKeyCode::PageDown) => log.history_offset = log.history_offset.saturating_sub(LINES),
+            _ => {}
+        }
+        log.history_offset = log.history_offset.min(max_offset);
+
+        ctx.print_color_centered(1, YELLOW, BLACK, "Message History");
+        let end = log.entries.len() - log.history_offset;
+        let start = end.saturating_sub(LINES);
+        log.entries[start..end]
+            .iter()
+            .enumerate()
+            .for_each(|(i, entry)| {
+                ctx.print_color(2, 3 + i as i32, entry.kind.colour(), BLACK, &entry.text);
+            });
+        ctx.print_color_centered(
+            SCREEN_HEIGHT * 2 - 2,
+            GREY,
+            BLACK,
+            "Up/Down/PgUp/PgDn to scroll, Escape to return."
+        );
+
+        if let Some(VirtualKeyCode::Escape) = ctx.key {
+            log.history_offset = 0;
+            drop(log);
+            self.resources.insert(TurnState::AwaitingInput);
+        }
+    }
+
+    fn show_inventory(&mut self, ctx: &mut BTerm) {
+        ctx.set_active_console(2);
+        const LINES: usize = 30;
+
+        let player = *<Entity>::query()
+            .filter(component::<Player>())
+            .iter(&self.ecs)
+            .next()
+            .unwrap();
+        let identification = self.resources.get::<Identification>().unwrap().clone();
+        let items = carried_items(&self.ecs, player, &identification);
+        let mut menu = *self.resources.get::<InventoryMenu>().unwrap();
+
+        match ctx.key {
+            Some(VirtualKeyCode::Up) => menu.select(menu.selected.saturating_sub(1), items.len(), LINES),
+            Some(VirtualKeyCode::Down) => menu.select(menu.selected + 1, items.len(), LINES),
+            Some(VirtualKeyCode::PageUp) => menu.select(menu.selected.saturating_sub(LINES), items.len(), LINES),
+            Some(VirtualKeyCode::PageDown) => menu.select(menu.selected + LINES, items.len(), LINES),
+            Some(VirtualKeyCode::Return) => menu.inspecting = !menu.inspecting,
+            _ => menu.select(menu.selected, items.len(), LINES)
+        }
+
+        ctx.print_color_centered(
+            1,
+            YELLOW,
+            BLACK,
+            format!("Inventory ({} / {})", items.len(), INVENTORY_CAPACITY)
+        );
+        if items.is_empty() {
+            ctx.print_color_centered(3, GREY, BLACK, "Your backpack is empty.");
+        }
+        items
+            .iter()
+            .enumerate()
+            .skip(menu.offset)
+            .take(LINES)
+            .for_each(|(i, (item, name))| {
+                let y = 3 + (i - menu.offset) as i32;
+                let label = match self.ecs.entry_ref(*item).unwrap().get_component::<Equipped>() {
+                    Ok(equipped) => format!("{} ({})", name, equipped.slot.describe()),
+                    Err(_) => name.clone()
+                };
+                if i == menu.selected {
+                    ctx.print_color(2, y, YELLOW, BLACK, format!("> {}", label));
+                } else {
+                    ctx.print_color(2, y, WHITE, BLACK, format!("  {}", label));
+                }
+            });
+        if menu.offset + LINES < items.len() {
+            ctx.print_color(4, 3 + LINES as i32, GREY, BLACK, "...");
+        }
+
+        let selected = items.get(menu.selected).map(|(item, _)| *item);
+        if let Some(item) = selected {
+            let entry = self.ecs.entry_ref(item).unwrap();
+            let known = entry
+                .get_component::<Name>()
+                .map(|name| identification.is_known(&name.0))
+                .unwrap_or(true);
+            let mut y = 5 + LINES as i32;
+            if !known {
+                ctx.print_color(2, y, GREY, BLACK, "You do not know what this is yet.");
+            } else if let Ok(description) = entry.get_component::<Description>() {
+                ctx.print_color(2, y, WHITE, BLACK, &description.0);
+                y += 1;
+            }
+
+            // inspecting lists what the item actually does
+            if menu.inspecting && known {
+                if let Ok(equippable) = entry.get_component::<Equippable>() {
+                    ctx.print_color(2, y, CYAN, BLACK, format!("Worn in the {} slot.", equippable.0.describe()));
+                    y += 1;
+                }
+                if let Ok(damage) = entry.get_component::<Damage>() {
+                    ctx.print_color(2, y, CYAN, BLACK, format!("Adds {} damage.", damage.0));
+                    y += 1;
+                }
+                if entry.get_component::<Appraised>().is_ok() {
+                    if let Ok(enchantment) = entry.get_component::<Enchantment>() {
+                        ctx.print_color(2, y, CYAN, BLACK, format!("Enchanted {:+}.", enchantment.0));
+                        y += 1;
+                    }
+                    if entry.get_component::<Cursed>().is_ok() {
+                        ctx.print_color(2, y, RED, BLACK, "Cursed, it cannot be taken off.");
+                        y += 1;
+                    }
+                }
+                if let Ok(provides) = entry.get_component::<Provides>() {
+                    provides.0.iter().for_each(|effect| {
+                        ctx.print_color(2, y, CYAN, BLACK, effect.describe());
+                        y += 1;
+                    });
+                }
+            }
+        }
+
+        ctx.print_color_centered(
+            SCREEN_HEIGHT * 2 - 2,
+            GREY,
+            BLACK,
+            "Up/Down select, U use, E equip, D drop, Enter inspect, Escape return."
+        );
+
+        // using, equipping or dropping an item takes a turn
+        let equippable = selected
+            .map(|item| self.ecs.entry_ref(item).unwrap().get_component::<Equippable>().is_ok())
+            .unwrap_or(false);
+        let ranged = selected
+            .map(|item| self.ecs.entry_ref(item).unwrap().get_component::<Ranged>().is_ok())
+            .unwrap_or(false);
+        let action = match (ctx.key, selected) {
+            (Some(VirtualKeyCode::U), Some(item)) if equippable => Some(self.ecs.push(((), WantsToEquip {
+                entity: player,
+                item
+            }))),
+            (Some(VirtualKeyCode::U), Some(item)) if ranged && out_of_charges(&self.ecs, item) => {
+                let name = item_name(&self.ecs, item, &self.resources.get::<Identification>().unwrap());
+                self.resources
+                    .get_mut::<GameLog>()
+                    .unwrap()
+                    .add(LogKind::Item, format!("The {} has no charges left.", name));
+                None
+            },
+            (Some(VirtualKeyCode::U), Some(item)) if ranged => {
+                // aimed items pick a target before the turn is taken
+                self.resources.get_mut::<Targeting>().unwrap().begin(&self.ecs, player, item);
+                menu.inspecting = false;
+                self.resources.insert(menu);
+                self.resources.insert(TurnState::Targeting);
+                return;
+            },
+            (Some(VirtualKeyCode::U), Some(item)) => Some(self.ecs.push(((), ActivateItem {
+                used_by: player,
+                item,
+                target: None
+            }))),
+            (Some(VirtualKeyCode::E), Some(item)) if equippable => Some(self.ecs.push(((), WantsToEquip {
+                entity: player,
+                item
+            }))),
+            (Some(VirtualKeyCode::E), Some(_)) => {
+                self.resources.get_mut::<GameLog>().unwrap().add(LogKind::Item, "You cannot equip that.");
+                None
+            },
+            (Some(VirtualKeyCode::D), Some(item)) => Some(self.ecs.push(((), WantsToDrop {
+                entity: player,
+                item
+            }))),
+            _ => None
+        };
+
+        if action.is_some() {
+            menu.inspecting = false;
+            self.resources.insert(TurnState::PlayerTurn);
+        } else if let Some(VirtualKeyCode::Escape) = ctx.key {
+            menu.inspecting = false;
+            self.resources.insert(TurnState::AwaitingInput);
+        }
+        self.resources.insert(menu);
+    }
+
+    fn show_shop(&mut self, ctx: &mut BTerm) {
+        ctx.set_active_console(2);
+
+        let (player, gold) = <(Entity, &Wallet)>::query()
+            .filter(component::<Player>())
+            .iter(&self.ecs)
+            .map(|(entity, wallet)| (*entity, wallet.0))
+            .next()
+            .unwrap();
+        let mut menu = *self.resources.get::<ShopMenu>().unwrap();
+        let shopkeeper = match menu.shopkeeper {
+            Some(shopkeeper) => shopkeeper,
+            None => {
+                self.resources.insert(TurnState::AwaitingInput);
+                return;
+            }
+        };
+        let identification = self.resources.get::<Identification>().unwrap().clone();
+        let items = wares(&self.ecs, shopkeeper, &identification);
+
+        if !menu.confirming {
+            match ctx.key {
+                Some(VirtualKeyCode::Up) => menu.selected = menu.selected.saturating_sub(1),
+                Some(VirtualKeyCode::Down) => menu.selected += 1,
+                Some(VirtualKeyCode::Return) if !items.is_empty() => menu.confirming = true,
+                _ => {}
+            }
+        }
+        menu.selected = menu.selected.min(items.len().saturating_sub(1));
+
+        ctx.print_color_centered(1, YELLOW, BLACK, "\"Take a look at my wares.\"");
+        ctx.print_color_centered(2, GOLD, BLACK, format!("You have {} gold.", gold));
+        if items.is_empty() {
+            ctx.print_color_centered(4, GREY, BLACK, "The shelves are bare.");
+        }
+        items
+            .iter()
+            .enumerate()
+            .for_each(|(i, (_, name, price))| {
+                let y = 4 + i as i32;
+                let colour = if *price > gold { GREY } else { WHITE };
+                if i == menu.selected {
+                    ctx.print_color(2, y, YELLOW, BLACK, format!("> {} - {} gold", name, price));
+                } else {
+                    ctx.print_color(2, y, colour, BLACK, format!("  {} - {} gold", name, price));
+                }
+            });
+
+        let selected = items.get(menu.selected);
+        if menu.confirming && let Some((_, name, price)) = selected {
+            ctx.print_color_centered(
+                6 + items.len() as i32,
+                CYAN,
+                BLACK,
+                format!("Buy the {} for {} gold? (Y/N)", name, price)
+            );
+        }
+
+        ctx.print_color_centered(
+            SCREEN_HEIGHT * 2 - 2,
+            GREY,
+            BLACK,
+            "Up/Down select, Enter buy, Escape leave."
+        );
+
+        match (ctx.key, selected) {
+            (Some(VirtualKeyCode::Y), Some((item, _, price))) if menu.confirming => {
+                menu.confirming = false;
+                if *price > gold {
+                    self.resources.get_mut::<GameLog>().unwrap().add(LogKind::Item, "You cannot afford that.");
+                } else {
+                    // paying for an item takes a turn
+                    self.ecs.push(((), WantsToBuy {
+                        buyer: player,
+                        item: *item
+                    }));
+                    self.resources.insert(TurnState::PlayerTurn);
+                }
+            },
+            (Some(VirtualKeyCode::N), _) | (Some(VirtualKeyCode::Escape), _) if menu.confirming => {
+                menu.confirming = false;
+            },
+            (Some(VirtualKeyCode::Escape), _) => {
+                self.resources.insert(TurnState::AwaitingInput);
+            },
+            _ => {}
+        }
+        self.resources.insert(menu);
+    }
+
     fn advance_level(&mut self) {
         let player_entity = *<Entity>::query()
             .filter(component::<Player>())
@@ -144,16 +452,62 @@ impl State {
                 pos.y = map_builder.player_start.y;
             });
 
+        let theme = self.resources
+            .get::<Themes>()
+            .unwrap()
+            .pick(map_level as usize, &mut rng)
+            .clone();
+
         // spawn the amulet or player
         if map_level == 2 {
+            map_builder.build_arena();
             spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start);
         } else {
             let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
             map_builder.map.tiles[exit_idx] = TileType::Exit;
+            if rng.range(0, SHOP_CHANCE) == 0 {
+                map_builder.build_shop(&mut rng);
+            }
         }
         
-        spawn_level(&mut self.ecs, &mut rng, map_level as usize, &map_builder.monster_spawns);
-        self.add_default_resources(map_builder);
+        spawn_level(
+            &mut self.ecs,
+            &map_builder.map,
+            &self.resources.get::<Templates>().unwrap(),
+            &mut rng,
+            map_level as usize,
+            &map_builder.monster_spawns,
+            &theme.monsters
+        );
+        spawn_torches(&mut self.ecs, &map_builder.map, &mut rng);
+        if let Some(shop) = &map_builder.shop {
+            spawn_shop(
+                &mut self.ecs,
+                &self.resources.get::<Templates>().unwrap(),
+                shop,
+                &mut rng,
+                map_level as usize
+            );
+        }
+        if let Some(boss_start) = map_builder.boss_start {
+            self.resources.get::<Templates>().unwrap().spawn_boss(
+                &mut self.ecs,
+                &boss_start,
+                &mut rng,
+                map_level as usize
+            );
+        }
+
+        let mut log = self.resources.get_mut::<GameLog>().unwrap();
+        log.add(
+            LogKind::Level,
+            format!("You descend into the {} on dungeon level {}.", theme.name.to_lowercase(), map_level)
+        );
+        if map_builder.boss_start.is_some() {
+            log.add(LogKind::Level, "You sense something ancient guarding the amulet.");
+        }
+        drop(log);
+        self.add_default_resources(map_builder, Box::new(theme));
     }
 
     pub fn reset(&mut self) {
@@ -170,15 +524,49 @@ impl State {
         // spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start);
         let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
         map_builder.map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(&mut self.ecs, &mut rng, 0, &map_builder.monster_spawns);
-        self.add_default_resources(map_builder);
+        if rng.range(0, SHOP_CHANCE) == 0 {
+            map_builder.build_shop(&mut rng);
+        }
+        // templates are loaded once per game and kept for loot drops
+        let templates = Templates::load();
+        let themes = Themes::load();
+        themes.validate(&templates);
+        let theme = themes.pick(0, &mut rng).clone();
+        spawn_level(
+            &mut self.ecs,
+            &map_builder.map,
+            &templates,
+            &mut rng,
+            0,
+            &map_builder.monster_spawns,
+            &theme.monsters
+        );
+        spawn_torches(&mut self.ecs, &map_builder.map, &mut rng);
+        if let Some(shop) = &map_builder.shop {
+            spawn_shop(&mut self.ecs, &templates, shop, &mut rng, 0);
+        }
+        self.resources.insert(Identification::new(&templates, &mut rng));
+        self.resources.insert(templates);
+        self.resources.insert(Factions::load());
+        self.resources.insert(themes);
+        let mut log = GameLog::default();
+        log.add(LogKind::Info, "Welcome to the dungeon. Find the Amulet of Yala!");
+        self.resources.insert(log);
+        self.add_default_resources(map_builder, Box::new(theme));
     }
 
-    fn add_default_resources(&mut self, map_builder: MapBuilder) {
-        self.resources.insert(map_builder.map);
+    fn add_default_resources(&mut self, map_builder: MapBuilder, theme: Box<dyn MapTheme>) {
+        // light the new level before the first frame is drawn
+        let mut map = map_builder.map;
+        light_map(&self.ecs, &mut map);
+        self.resources.insert(map);
         self.resources.insert(Camera::new(map_builder.player_start));
+        self.resources.insert(FlowFields::default());
+        self.resources.insert(InventoryMenu::default());
+        self.resources.insert(ShopMenu::default());
+        self.resources.insert(Targeting::default());
         self.resources.insert(TurnState::AwaitingInput);
-        self.resources.insert(map_builder.theme);
+        self.resources.insert(theme);
     }
 }
 
@@ -205,6 +593,9 @@ impl GameState for State {
             TurnState::AwaitingInput => self
                 .input_systems
                 .execute(&mut self.ecs, &mut self.resources),
+            TurnState::Targeting => self
+                .targeting_systems
+                .execute(&mut self.ecs, &mut self.resources),
             TurnState::PlayerTurn => self
                 .player_systems
                 .execute(&mut self.ecs, &mut self.resources),
@@ -214,6 +605,9 @@ impl GameState for State {
             TurnState::GameOver => self.game_over(ctx),
             TurnState::Victory => self.victory(ctx),
             TurnState::NextLevel => self.advance_level(),
+            TurnState::ShowingLog => self.show_log(ctx),
+            TurnState::ShowingInventory => self.show_inventory(ctx),
+            TurnState::Shopping => self.show_shop(ctx),
         }
 
         render_draw_buffer(ctx).expect("Render error");
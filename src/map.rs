@@ -11,14 +11,19 @@ pub enum TileType {
 
 pub struct Map {
     pub tiles: Vec<TileType>,
-    pub revealed_tiles: Vec<bool>
+    pub revealed_tiles: Vec<bool>,
+    // how brightly each tile is lit from 0 to 1, and the colour of the light
+    pub light: Vec<f32>,
+    pub light_colour: Vec<RGB>
 }
 
 impl Map {
     pub fn new() -> Self {
         Self {
             tiles: vec![TileType::Floor; NUM_TILES],
-            revealed_tiles: vec![false; NUM_TILES]
+            revealed_tiles: vec![false; NUM_TILES],
+            light: vec![1.0; NUM_TILES],
+            light_colour: vec![RGB::named(WHITE); NUM_TILES]
         }
     }
 
@@ -41,6 +46,11 @@ impl Map {
         }
     }
 
+    // distance from the given tiles to every reachable tile
+    pub fn dijkstra_from(&self, starts: &[usize]) -> DijkstraMap {
+        DijkstraMap::new(SCREEN_WIDTH, SCREEN_HEIGHT, starts, self, 1024.0)
+    }
+
     fn valid_exit(&self, loc: Point, delta: Point) -> Option<usize> {
         let destination = loc + delta;
         if self.in_bounds(destination) {
@@ -24,13 +24,7 @@ impl MapArchitect for DrunkardsWalkArchitect {
             self.drunkard(&next_start, rng, &mut mb.map);
 
             // ensure the next area is accesible
-            let dijkstra_map = DijkstraMap::new(
-                SCREEN_WIDTH,
-                SCREEN_HEIGHT,
-                &[mb.map.point2d_to_index(centre)],
-                &mb.map,
-                1024.0
-            );
+            let dijkstra_map = mb.map.dijkstra_from(&[mb.map.point2d_to_index(centre)]);
 
             dijkstra_map.map
                 .iter()
@@ -1,9 +1,8 @@
 use crate::prelude::*;
 use automata::CellularAutomataArchitect;
 use drunkard::DrunkardsWalkArchitect;
-use prefab::apply_prefab;
+use prefab::{apply_arena, apply_prefab, apply_shop};
 use rooms::RoomArchitect;
-use themes::*;
 
 mod automata;
 mod drunkard;
@@ -12,24 +11,36 @@ mod prefab;
 mod rooms;
 mod themes;
 
+pub use themes::Themes;
+
 trait MapArchitect {
     fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder;
 }
 
 pub trait MapTheme : Sync + Send {
     fn tile_to_render(&self, tile_type: TileType) -> FontCharType;
+    // foreground and background of the tile in full light
+    fn tile_colours(&self, tile_type: TileType) -> (RGB, RGB);
 }
 
 const NUM_ROOMS: usize = 20;
 const NUM_MONSTERS : usize = 50;
 
+// where a shop put its keeper and the spots its wares are laid out on
+pub struct ShopLayout {
+    pub keeper: Point,
+    pub stock: Vec<Point>
+}
+
 pub struct MapBuilder {
     pub map: Map,
     pub rooms: Vec<Rect>,
     pub monster_spawns: Vec<Point>,
     pub player_start: Point,
     pub amulet_start: Point,
-    pub theme: Box<dyn MapTheme>
+    // only set on the final level, where the amulet is guarded
+    pub boss_start: Option<Point>,
+    pub shop: Option<ShopLayout>
 }
 
 impl MapBuilder {
@@ -41,13 +52,17 @@ impl MapBuilder {
         };
         let mut mb = architect.new(rng);
         apply_prefab(&mut mb, rng);
-        mb.theme = match rng.range(0, 2) {
-            0 => DungeonTheme::new(),
-            _ => ForestTheme::new()
-        };
         mb
     }
 
+    pub fn build_arena(&mut self) {
+        apply_arena(self);
+    }
+
+    pub fn build_shop(&mut self, rng: &mut RandomNumberGenerator) {
+        apply_shop(self, rng);
+    }
+
     pub fn default() -> Self {
         Self {
             map: Map::new(),
@@ -55,7 +70,8 @@ impl MapBuilder {
             monster_spawns: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
-            theme: DungeonTheme::new()
+            boss_start: None,
+            shop: None
         }
     }
 
@@ -64,13 +80,7 @@ impl MapBuilder {
     }
 
     fn find_most_distant(&self) -> Point {
-        let dijkstra_map = DijkstraMap::new(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
-            &[self.map.point2d_to_index(self.player_start)],
-            &self.map,
-            1024.0
-        );
+        let dijkstra_map = self.map.dijkstra_from(&[self.map.point2d_to_index(self.player_start)]);
 
         const UNREACHABLE: &f32 = &f32::MAX;
         self.map.index_to_point2d
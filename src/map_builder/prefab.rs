@@ -14,16 +14,36 @@ const FORTRESS : (&str, i32, i32) = ("
 ------------
 ", 12, 11);
 
+const ARENA : (&str, i32, i32) = ("
+-------------
+-###########-
+-#---------#-
+-#-#-----#-#-
+-#----B----#-
+-#---------#-
+-#----A----#-
+-#---------#-
+-#-#-----#-#-
+-#---------#-
+-#####-#####-
+-------------
+", 13, 12);
+
+const SHOP : (&str, i32, i32) = ("
+-----------
+-#########-
+-#-$-$-$-#-
+-#-------#-
+-#-$-K-$-#-
+-#-------#-
+-####-####-
+-----------
+", 11, 8);
+
 pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
     let mut placement = None;
 
-    let dijkstra_map = DijkstraMap::new(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
-        &[mb.map.point2d_to_index(mb.player_start)],
-        &mb.map,
-        1024.0
-    );
+    let dijkstra_map = mb.map.dijkstra_from(&[mb.map.point2d_to_index(mb.player_start)]);
 
     let mut attempts = 0;
     while placement.is_none() && attempts < 10 {
@@ -77,3 +97,110 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
         }
     }
 }
+
+// the final level walls the amulet into an arena, with its guardian in front of it
+pub fn apply_arena(mb: &mut MapBuilder) {
+    let placement = Point::new(
+        (mb.amulet_start.x - ARENA.1 / 2).clamp(1, SCREEN_WIDTH - ARENA.1 - 1),
+        (mb.amulet_start.y - ARENA.2 / 2).clamp(1, SCREEN_HEIGHT - ARENA.2 - 1)
+    );
+    let dimensions = Rect::with_size(placement.x, placement.y, ARENA.1, ARENA.2);
+    if dimensions.point_in_rect(mb.player_start) {
+        // too close to the player to build, the guardian waits on the amulet
+        mb.boss_start = Some(mb.amulet_start);
+        return;
+    }
+
+    let points = dimensions.point_set();
+    mb.monster_spawns.retain(|pt| !points.contains(pt));
+
+    let string_vec: Vec<char> = ARENA.0
+        .chars()
+        .filter(|a| *a != '\r' && *a != '\n')
+        .collect();
+
+    let mut i = 0;
+    for ty in placement.y..placement.y + ARENA.2 {
+        for tx in placement.x..placement.x + ARENA.1 {
+            let idx = map_idx(tx, ty);
+            let c = string_vec[i];
+            match c {
+                'A' => {
+                    mb.map.tiles[idx] = TileType::Floor;
+                    mb.amulet_start = Point::new(tx, ty);
+                },
+                'B' => {
+                    mb.map.tiles[idx] = TileType::Floor;
+                    mb.boss_start = Some(Point::new(tx, ty));
+                },
+                '-' => mb.map.tiles[idx] = TileType::Floor,
+                '#' => mb.map.tiles[idx] = TileType::Wall,
+                _ => print!("No idea what to do with [{}]", c)
+            }
+            i += 1;
+        }
+    }
+}
+
+// a walled shop with its keeper in the middle of the wares, if a spot can be found
+pub fn apply_shop(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+    let dijkstra_map = mb.map.dijkstra_from(&[mb.map.point2d_to_index(mb.player_start)]);
+
+    let mut placement = None;
+    let mut attempts = 0;
+    while placement.is_none() && attempts < 10 {
+        let dimensions = Rect::with_size(
+            rng.range(1, SCREEN_WIDTH - SHOP.1 - 1),
+            rng.range(1, SCREEN_HEIGHT - SHOP.2 - 1),
+            SHOP.1,
+            SHOP.2
+        );
+
+        // the shop must be reachable, and must not bury the start or the exit
+        let reachable = dimensions.point_set().iter().any(|pt| {
+            let distance = dijkstra_map.map[mb.map.point2d_to_index(*pt)];
+            distance < 2000.0 && distance > 10.0
+        });
+        if reachable
+            && !dimensions.point_in_rect(mb.player_start)
+            && !dimensions.point_in_rect(mb.amulet_start)
+        {
+            placement = Some(Point::new(dimensions.x1, dimensions.y1));
+            let points = dimensions.point_set();
+            mb.monster_spawns.retain(|pt| !points.contains(pt));
+        }
+        attempts += 1;
+    }
+
+    if let Some(placement) = placement {
+        let string_vec: Vec<char> = SHOP.0
+            .chars()
+            .filter(|a| *a != '\r' && *a != '\n')
+            .collect();
+
+        let mut keeper = placement;
+        let mut stock = Vec::new();
+        let mut i = 0;
+        for ty in placement.y..placement.y + SHOP.2 {
+            for tx in placement.x..placement.x + SHOP.1 {
+                let idx = map_idx(tx, ty);
+                let c = string_vec[i];
+                match c {
+                    'K' => {
+                        mb.map.tiles[idx] = TileType::Floor;
+                        keeper = Point::new(tx, ty);
+                    },
+                    '$' => {
+                        mb.map.tiles[idx] = TileType::Floor;
+                        stock.push(Point::new(tx, ty));
+                    },
+                    '-' => mb.map.tiles[idx] = TileType::Floor,
+                    '#' => mb.map.tiles[idx] = TileType::Wall,
+                    _ => print!("No idea what to do with [{}]", c)
+                }
+                i += 1;
+            }
+        }
+        mb.shop = Some(ShopLayout { keeper, stock });
+    }
+}
@@ -1,37 +1,82 @@
 use crate::prelude::*;
+use ron::de::from_reader;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs::File;
 
-pub struct DungeonTheme {}
+// how a tile is drawn in full light
+#[derive(Clone, Deserialize, Debug)]
+pub struct TileStyle {
+    pub glyph: char,
+    pub fg: (u8, u8, u8),
+    pub bg: (u8, u8, u8),
+}
 
-impl DungeonTheme {
-    pub fn new() -> Box<dyn MapTheme> {
-        Box::new(Self{})
-    }
+#[derive(Clone, Deserialize, Debug)]
+pub struct Theme {
+    pub name: String,
+    // the dungeon levels this theme can be chosen for
+    pub depths: HashSet<usize>,
+    pub floor: TileStyle,
+    pub wall: TileStyle,
+    pub exit: TileStyle,
+    // monsters spawned at random on the level, an empty pool allows any of them
+    pub monsters: Vec<String>,
 }
 
-impl MapTheme for DungeonTheme {
-   fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
-       match tile_type {
-           TileType::Floor => to_cp437('.'),
-           TileType::Wall => to_cp437('#'),
-           TileType::Exit => to_cp437('>'),
-       }
-   } 
+impl Theme {
+    fn style(&self, tile_type: TileType) -> &TileStyle {
+        match tile_type {
+            TileType::Floor => &self.floor,
+            TileType::Wall => &self.wall,
+            TileType::Exit => &self.exit,
+        }
+    }
 }
 
-pub struct ForestTheme {}
+impl MapTheme for Theme {
+    fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
+        to_cp437(self.style(tile_type).glyph)
+    }
 
-impl ForestTheme {
-    pub fn new() -> Box<dyn MapTheme> {
-        Box::new(Self{})
+    fn tile_colours(&self, tile_type: TileType) -> (RGB, RGB) {
+        let style = self.style(tile_type);
+        (RGB::named(style.fg), RGB::named(style.bg))
     }
 }
 
-impl MapTheme for ForestTheme {
-    fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
-        match tile_type {
-            TileType::Floor => to_cp437(';'),
-            TileType::Wall => to_cp437('"'),
-            TileType::Exit => to_cp437('>'),
-        }
+#[derive(Clone, Deserialize, Debug)]
+pub struct Themes {
+    pub themes: Vec<Theme>
+}
+
+impl Themes {
+    pub fn load() -> Self {
+        let file = File::open("resources/themes.ron")
+            .expect("Failed to open themes file");
+        from_reader(file).expect("Failed to parse themes file")
+    }
+
+    // every monster in a pool has to be one that can spawn at random at one of
+    // the theme's depths, or the theme would quietly leave its levels empty
+    pub fn validate(&self, templates: &Templates) {
+        self.themes.iter().for_each(|theme| {
+            theme.monsters.iter().for_each(|name| {
+                if !templates.spawns_at_random(name, &theme.depths) {
+                    panic!("{} theme has a monster that cannot spawn there: {}", theme.name, name);
+                }
+            });
+        });
+    }
+
+    // a random theme from those allowed at this depth
+    pub fn pick(&self, level: usize, rng: &mut RandomNumberGenerator) -> &Theme {
+        let available: Vec<&Theme> = self.themes
+            .iter()
+            .filter(|t| t.depths.contains(&level))
+            .collect();
+        rng.random_slice_entry(&available)
+            .copied()
+            .unwrap_or_else(|| panic!("No theme for dungeon level {}", level))
     }
 }
@@ -0,0 +1,36 @@
+use crate::prelude::*;
+
+// the shop screen, opened by bumping into a shopkeeper
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShopMenu {
+    pub shopkeeper: Option<Entity>,
+    pub selected: usize,
+    // waiting for the player to confirm buying the selected item
+    pub confirming: bool
+}
+
+impl ShopMenu {
+    pub fn open(shopkeeper: Entity) -> Self {
+        Self {
+            shopkeeper: Some(shopkeeper),
+            ..Self::default()
+        }
+    }
+}
+
+// items the shopkeeper still has for sale, with the name to show and their
+// price, sorted by name like the inventory
+pub fn wares<W: EntityStore>(
+    ecs: &W,
+    shopkeeper: Entity,
+    identification: &Identification
+) -> Vec<(Entity, String, i32)> {
+    let mut items: Vec<(Entity, String, i32)> = <(Entity, &Name, &ForSale)>::query()
+        .filter(component::<Point>())
+        .iter(ecs)
+        .filter(|(_, _, sale)| sale.shopkeeper == shopkeeper)
+        .map(|(entity, _, sale)| (*entity, item_name(ecs, *entity, identification), sale.price))
+        .collect();
+    items.sort_by(|a, b| a.1.cmp(&b.1));
+    items
+}
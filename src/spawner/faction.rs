@@ -0,0 +1,41 @@
+use crate::prelude::*;
+use ron::de::from_reader;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs::File;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct FactionTemplate {
+    pub name: String,
+    pub hostile_to: HashSet<String>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Factions {
+    pub factions: Vec<FactionTemplate>
+}
+
+impl Factions {
+    pub fn load() -> Self {
+        let file = File::open("resources/factions.ron")
+            .expect("Failed to open factions file");
+        from_reader(file).expect("Failed to parse factions file")
+    }
+
+    pub fn is_hostile(&self, from: &Faction, to: &Faction) -> bool {
+        self.factions
+            .iter()
+            .find(|f| f.name == from.0)
+            .is_some_and(|f| f.hostile_to.contains(&to.0))
+    }
+
+    // entities without a faction never pick a fight
+    pub fn entity_is_hostile(&self, ecs: &SubWorld, from: Entity, to: Entity) -> bool {
+        let from = ecs.entry_ref(from).ok().and_then(|e| e.get_component::<Faction>().ok().cloned());
+        let to = ecs.entry_ref(to).ok().and_then(|e| e.get_component::<Faction>().ok().cloned());
+        match (from, to) {
+            (Some(from), Some(to)) => self.is_hostile(&from, &to),
+            _ => false
+        }
+    }
+}
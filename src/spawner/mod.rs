@@ -1,10 +1,19 @@
+mod faction;
 mod template;
 
 use crate::prelude::*;
-use crate::spawner::template::Templates;
+pub use crate::spawner::faction::Factions;
+pub use crate::spawner::template::Templates;
+
+// turns between each point of health the player recovers on a full stomach
+const PLAYER_REGEN_INTERVAL: i32 = 10;
+// the player always carries enough light to see a few steps in the dark
+const PLAYER_LIGHT_RADIUS: i32 = 3;
+const TORCHES_PER_LEVEL: usize = 12;
+const TORCH_LIGHT_RADIUS: i32 = 5;
 
 pub fn spawn_player(ecs: &mut World, pos: Point) {
-    ecs.push(
+    let player = ecs.push(
         (
             Player {
                 map_level: 0
@@ -19,9 +28,21 @@ pub fn spawn_player(ecs: &mut World, pos: Point) {
                 max: 10
             },
             FieldOfView::new(8),
-            Damage(1)
+            Damage(1),
+            Experience::default(),
+            Faction("Player".to_string())
         )
     );
+    // legion only builds entities from tuples of up to eight components
+    if let Some(mut entry) = ecs.entry(player) {
+        entry.add_component(Hunger { food: Hunger::START });
+        entry.add_component(Regeneration::new(PLAYER_REGEN_INTERVAL));
+        entry.add_component(Wallet(0));
+        entry.add_component(LightSource {
+            radius: PLAYER_LIGHT_RADIUS,
+            colour: RGB::named(WHITE)
+        });
+    }
 }
 
 pub fn spawn_amulet_of_yala(ecs: &mut World, pos: Point) {
@@ -34,17 +55,84 @@ pub fn spawn_amulet_of_yala(ecs: &mut World, pos: Point) {
                 colour: ColorPair::new(WHITE, BLACK),
                 glyph: to_cp437('|')
             },
-            Name("Amulet of Yala".to_string())
+            Name("Amulet of Yala".to_string()),
+            Description("The amulet you came for, it is said to protect your town.".to_string())
         )
     );
 }
 
 pub fn spawn_level(
     ecs: &mut World,
+    map: &Map,
+    templates: &Templates,
     rng: &mut RandomNumberGenerator,
     level: usize,
-    spawn_points: &[Point]
+    spawn_points: &[Point],
+    monsters: &[String]
+) {
+    templates.spawn_entities(ecs, map, rng, level, spawn_points, monsters);
+}
+
+// torches burn on floor tiles against the walls, lighting the rooms around them
+pub fn spawn_torches(ecs: &mut World, map: &Map, rng: &mut RandomNumberGenerator) {
+    let mut spots: Vec<Point> = map.tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, tile)| **tile == TileType::Floor)
+        .map(|(idx, _)| map.index_to_point2d(idx))
+        .filter(|pt| {
+            [Point::new(-1, 0), Point::new(1, 0), Point::new(0, -1), Point::new(0, 1)]
+                .iter()
+                .any(|delta| map.try_idx(*pt + *delta)
+                    .is_some_and(|idx| map.tiles[idx] == TileType::Wall))
+        })
+        .collect();
+
+    for _ in 0..TORCHES_PER_LEVEL {
+        if spots.is_empty() {
+            break;
+        }
+        let pos = spots.remove(rng.random_slice_index(&spots).unwrap());
+        ecs.push(
+            (
+                pos,
+                Render {
+                    colour: ColorPair::new(ORANGE, BLACK),
+                    glyph: to_cp437('*')
+                },
+                Name("Torch".to_string()),
+                LightSource {
+                    radius: TORCH_LIGHT_RADIUS,
+                    colour: RGB::named(ORANGE)
+                }
+            )
+        );
+    }
+}
+
+// the shopkeeper stands among its wares and is on nobody's side
+pub fn spawn_shop(
+    ecs: &mut World,
+    templates: &Templates,
+    shop: &ShopLayout,
+    rng: &mut RandomNumberGenerator,
+    level: usize
 ) {
-    let template = Templates::load();
-    template.spawn_entities(ecs, rng, level, spawn_points);
-}
\ No newline at end of file
+    let shopkeeper = ecs.push(
+        (
+            Shopkeeper,
+            shop.keeper,
+            Render {
+                colour: ColorPair::new(GOLD, BLACK),
+                glyph: to_cp437('@')
+            },
+            Name("Shopkeeper".to_string()),
+            Health {
+                current: 20,
+                max: 20
+            },
+            Faction("Shopkeepers".to_string())
+        )
+    );
+    templates.stock_shop(ecs, shopkeeper, &shop.stock, rng, level);
+}
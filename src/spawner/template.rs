@@ -6,6 +6,18 @@ use serde::Deserialize;
 use std::collections::HashSet;
 use std::fs::File;
 
+// the most a shop item can cost on the first level, and how much more each level allows
+const SHOP_BUDGET: i32 = 40;
+const SHOP_BUDGET_PER_LEVEL: i32 = 30;
+
+// chances out of 100 that equipment is cursed or enchanted, enchantment
+// becoming more likely on each level
+const CURSE_CHANCE: i32 = 15;
+const ENCHANT_CHANCE: i32 = 15;
+const ENCHANT_CHANCE_PER_LEVEL: i32 = 10;
+// each point of enchantment moves a shop price by a third of its base
+const ENCHANT_PRICE_STEPS: i32 = 3;
+
 #[derive(Clone, Deserialize, Debug)]
 pub struct Template {
     pub entity_type: EntityType,
@@ -13,9 +25,98 @@ pub struct Template {
     pub frequency: i32,
     pub name: String,
     pub glyph: char,
-    pub provides: Option<Vec<(String, i32)>>,
+    pub description: Option<String>,
+    pub provides: Option<Vec<Effect>>,
     pub hp: Option<i32>,
     pub base_damage: Option<i32>,
+    pub loot: Option<LootTable>,
+    pub corpse: Option<char>,
+    pub ai: Option<Behaviour>,
+    pub flee_threshold: Option<f32>,
+    pub perception: Option<i32>,
+    // percentage chance that the monster spawns asleep
+    pub sleep_chance: Option<i32>,
+    pub faction: Option<String>,
+    // extra copies of this monster spawned around it as a pack
+    pub companions: Option<i32>,
+    // monsters with spells are driven by the spellcasting system instead of their ai
+    pub spells: Option<Vec<SpellTemplate>>,
+    // casters back away from enemies that get closer than this
+    pub keep_distance: Option<i32>,
+    // unique monsters are never spawned at random, the level places them
+    pub unique: Option<bool>,
+    pub phases: Option<Vec<BossPhase>>,
+    // potions and scrolls are unidentified until used
+    pub category: Option<ItemCategory>,
+    // consumables that merge into a single inventory slot
+    pub stacks: Option<bool>,
+    // how far away an aimed item such as a fireball can be used
+    pub range: Option<i32>,
+    // wands hold this many uses and are not used up
+    pub charges: Option<i32>,
+    // where an item is worn, weapons default to the main hand
+    pub slot: Option<EquipmentSlot>,
+    // turns between each point of health a monster recovers
+    pub regen: Option<i32>,
+    // what the item costs in a shop, items without a price are never sold
+    pub price: Option<i32>,
+    // the most coins found in a pile of gold
+    pub gold: Option<i32>,
+    // lanterns light the way while equipped, glowing monsters light their surroundings
+    pub light: Option<LightTemplate>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct LightTemplate {
+    pub radius: i32,
+    pub colour: (u8, u8, u8),
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct SpellTemplate {
+    pub kind: SpellKind,
+    pub range: i32,
+    pub power: i32,
+    pub cooldown: i32,
+}
+
+// how an enemy acts on its turn, monsters chase the player unless told otherwise
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+pub enum Behaviour {
+    Chase,
+    Wander,
+    Stationary,
+    Coward,
+    Patrol
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct LootTable {
+    // weight of the chance that nothing is dropped at all
+    pub nothing: i32,
+    pub entries: Vec<LootEntry>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct LootEntry {
+    pub name: String,
+    pub weight: i32,
+    // extra weight added per dungeon level, so better loot drops deeper down
+    pub depth_bonus: Option<i32>,
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Eq, Hash)]
+pub enum ItemCategory {
+    Potion,
+    Scroll,
+    Wand
+}
+
+// the unidentified names handed out to items of a category each run
+#[derive(Clone, Deserialize, Debug)]
+pub struct AppearancePool {
+    pub category: ItemCategory,
+    pub names: Vec<String>,
 }
 
 #[derive(Clone, Deserialize, Debug, PartialEq)]
@@ -26,27 +127,116 @@ pub enum EntityType {
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Templates {
-    pub entities: Vec<Template>
+    pub entities: Vec<Template>,
+    pub appearances: Vec<AppearancePool>
 }
 
 impl Templates {
     pub fn load() -> Self {
         let file = File::open("resources/template.ron")
             .expect("Failed to open template file");
-        from_reader(file).expect("Failed to parse template file")
+        let templates: Self = from_reader(file).expect("Failed to parse template file");
+        templates.validate();
+        templates
+    }
+
+    // mistakes in the file that parsing cannot catch fail here rather than mid game
+    fn validate(&self) {
+        self.entities
+            .iter()
+            .filter_map(|t| t.loot.as_ref().map(|loot| (t, loot)))
+            .for_each(|(template, loot)| {
+                loot.entries
+                    .iter()
+                    .filter(|entry| !self.entities.iter().any(|t| t.name == entry.name))
+                    .for_each(|entry| {
+                        panic!("{} drops unknown loot: {}", template.name, entry.name)
+                    });
+            });
+
+        self.entities
+            .iter()
+            .filter_map(|t| t.spells.as_ref().map(|spells| (t, spells)))
+            .for_each(|(template, spells)| {
+                let unknown = spells
+                    .iter()
+                    .filter_map(|spell| match &spell.kind {
+                        SpellKind::Summon(name) => Some(name),
+                        _ => None
+                    })
+                    .find(|name| !self.is_enemy(name));
+                if let Some(name) = unknown {
+                    panic!("{} summons unknown monster: {}", template.name, name);
+                }
+            });
+
+        self.entities
+            .iter()
+            .filter_map(|t| t.phases.as_ref().map(|phases| (t, phases)))
+            .for_each(|(template, phases)| {
+                let unknown = phases
+                    .iter()
+                    .filter_map(|phase| match &phase.action {
+                        PhaseAction::Summon(name, _) => Some(name),
+                        _ => None
+                    })
+                    .find(|name| !self.is_enemy(name));
+                if let Some(name) = unknown {
+                    panic!("{} summons unknown monster: {}", template.name, name);
+                }
+            });
+
+        // every unidentified item needs an appearance of its own
+        self.entities
+            .iter()
+            .filter_map(|t| t.category)
+            .collect::<HashSet<ItemCategory>>()
+            .iter()
+            .for_each(|category| {
+                let items = self.entities.iter().filter(|t| t.category == Some(*category)).count();
+                let names = self.appearances
+                    .iter()
+                    .filter(|pool| pool.category == *category)
+                    .map(|pool| pool.names.len())
+                    .min()
+                    .unwrap_or(0);
+                if names < items {
+                    panic!("{:?} has {} items but only {} appearances", category, items, names);
+                }
+            });
+    }
+
+    fn is_enemy(&self, name: &str) -> bool {
+        self.entities.iter().any(|t| t.name == name && t.entity_type == EntityType::Enemy)
+    }
+
+    // whether the named monster can turn up at random on any of these levels
+    pub fn spawns_at_random(&self, name: &str, levels: &HashSet<usize>) -> bool {
+        self.entities.iter().any(|t| {
+            t.name == name
+                && t.entity_type == EntityType::Enemy
+                && !t.unique.unwrap_or(false)
+                && t.levels.iter().any(|level| levels.contains(level))
+        })
     }
 
     pub fn spawn_entities(
         &self,
         ecs: &mut World,
+        map: &Map,
         rng: &mut RandomNumberGenerator,
         level: usize,
-        spawn_points: &[Point]
+        spawn_points: &[Point],
+        monsters: &[String]
     ) {
         let mut available_entities = Vec::new();
         self.entities
             .iter()
-            .filter(|e| e.levels.contains(&level))
+            .filter(|e| e.levels.contains(&level) && !e.unique.unwrap_or(false))
+            // the level's theme can limit which monsters live there
+            .filter(|e| e.entity_type == EntityType::Item
+                || monsters.is_empty()
+                || monsters.contains(&e.name))
             .for_each(|t| {
                 // push n references to this entity, do not clone
                 for _ in 0..t.frequency {
@@ -55,20 +245,202 @@ impl Templates {
             });
 
         let mut commands = CommandBuffer::new(ecs);
-        spawn_points.iter().for_each(|pt| {
-            if let Some(entity) = rng.random_slice_entry(&mut available_entities) {
-                self.spawn_entity(pt, entity, &mut commands);
+        let mut used: HashSet<Point> = spawn_points.iter().copied().collect();
+        spawn_points.iter().enumerate().for_each(|(pack, pt)| {
+            if let Some(template) = rng.random_slice_entry(&mut available_entities) {
+                let leader = self.spawn_entity(pt, template, rng, level, &mut commands);
+
+                let companions = template.companions.unwrap_or(0);
+                if companions > 0 {
+                    commands.add_component(leader, Pack(pack));
+
+                    // companions take the free floor around their leader
+                    let mut spots: Vec<Point> = Rect::with_size(pt.x - 1, pt.y - 1, 3, 3)
+                        .point_set()
+                        .into_iter()
+                        .filter(|p| map.can_enter_tile(*p) && !used.contains(p))
+                        .collect();
+                    for _ in 0..companions {
+                        if spots.is_empty() {
+                            break;
+                        }
+                        let spot = spots.remove(rng.random_slice_index(&spots).unwrap());
+                        used.insert(spot);
+                        let companion = self.spawn_entity(&spot, template, rng, level, &mut commands);
+                        commands.add_component(companion, Pack(pack));
+                    }
+                }
             }
         });
         commands.flush(ecs);
     }
 
+    pub fn drop_loot(
+        &self,
+        pt: &Point,
+        monster: &str,
+        depth: usize,
+        rng: &mut RandomNumberGenerator,
+        commands: &mut CommandBuffer
+    ) {
+        let template = match self.entities.iter().find(|t| t.name == monster) {
+            Some(template) => template,
+            None => return
+        };
+
+        if let Some(glyph) = template.corpse {
+            commands.push(
+                (
+                    *pt,
+                    Render {
+                        colour: ColorPair::new(DARK_RED, BLACK),
+                        glyph: to_cp437(glyph),
+                    },
+                    Name(format!("{} corpse", template.name)),
+                )
+            );
+        }
+
+        let table = match &template.loot {
+            Some(table) => table,
+            None => return
+        };
+
+        let weighted: Vec<(&LootEntry, i32)> = table.entries
+            .iter()
+            .map(|e| (e, e.weight + e.depth_bonus.unwrap_or(0) * depth as i32))
+            .filter(|(_, weight)| *weight > 0)
+            .collect();
+
+        let total = table.nothing + weighted.iter().map(|(_, w)| w).sum::<i32>();
+        if total < 1 {
+            return;
+        }
+
+        let mut roll = rng.range(0, total);
+        if roll < table.nothing {
+            return;
+        }
+        roll -= table.nothing;
+
+        for (entry, weight) in weighted {
+            if roll < weight {
+                self.spawn_named(pt, &entry.name, rng, depth, commands);
+                return;
+            }
+            roll -= weight;
+        }
+    }
+
+    pub fn spawn_boss(
+        &self,
+        ecs: &mut World,
+        pt: &Point,
+        rng: &mut RandomNumberGenerator,
+        level: usize
+    ) {
+        let boss = self.entities
+            .iter()
+            .find(|t| t.unique.unwrap_or(false) && t.levels.contains(&level));
+
+        if let Some(template) = boss {
+            let mut commands = CommandBuffer::new(ecs);
+            self.spawn_entity(pt, template, rng, level, &mut commands);
+            commands.flush(ecs);
+        }
+    }
+
+    // fills the shop with items priced within what the depth can afford
+    pub fn stock_shop(
+        &self,
+        ecs: &mut World,
+        shopkeeper: Entity,
+        stock: &[Point],
+        rng: &mut RandomNumberGenerator,
+        level: usize
+    ) {
+        let budget = SHOP_BUDGET + SHOP_BUDGET_PER_LEVEL * level as i32;
+        let mut available = Vec::new();
+        self.entities
+            .iter()
+            .filter(|t| t.entity_type == EntityType::Item && t.levels.contains(&level))
+            .filter(|t| t.price.is_some_and(|price| price <= budget))
+            .for_each(|t| {
+                for _ in 0..t.frequency {
+                    available.push(t);
+                }
+            });
+
+        let mut commands = CommandBuffer::new(ecs);
+        let wares: Vec<(Entity, i32)> = stock
+            .iter()
+            .filter_map(|pt| {
+                rng.random_slice_entry(&available).map(|template| {
+                    (self.spawn_entity(pt, template, rng, level, &mut commands), template.price.unwrap())
+                })
+            })
+            .collect();
+        commands.flush(ecs);
+
+        // the shopkeeper knows what the enchantment rolled on each item is worth,
+        // and shows it openly rather than let the price give a curse away
+        wares.iter().for_each(|(item, price)| {
+            if let Some(mut entry) = ecs.entry(*item) {
+                let bonus = match entry.get_component::<Enchantment>() {
+                    Ok(enchantment) => {
+                        let bonus = enchantment.0;
+                        entry.add_component(Appraised);
+                        bonus
+                    }
+                    Err(_) => 0
+                };
+                let price = i32::max(1, price * (ENCHANT_PRICE_STEPS + bonus) / ENCHANT_PRICE_STEPS);
+                entry.add_component(ForSale { price, shopkeeper });
+            }
+        });
+    }
+
+    pub fn spawn_named(
+        &self,
+        pt: &Point,
+        name: &str,
+        rng: &mut RandomNumberGenerator,
+        level: usize,
+        commands: &mut CommandBuffer
+    ) -> Option<Entity> {
+        self.entities
+            .iter()
+            .find(|t| t.name == name)
+            .map(|template| self.spawn_entity(pt, template, rng, level, commands))
+    }
+
+    // minions called up by a caster or a boss arrive ready to fight, and cannot
+    // be farmed for experience or loot
+    pub fn spawn_minion(
+        &self,
+        pt: &Point,
+        name: &str,
+        summoner: Entity,
+        rng: &mut RandomNumberGenerator,
+        level: usize,
+        commands: &mut CommandBuffer
+    ) -> Option<Entity> {
+        let minion = self.spawn_named(pt, name, rng, level, commands)?;
+        commands.remove_component::<Asleep>(minion);
+        commands.remove_component::<GrantsExperience>(minion);
+        commands.remove_component::<DropsLoot>(minion);
+        commands.add_component(minion, Summoned { summoner });
+        Some(minion)
+    }
+
     fn spawn_entity(
         &self,
         pt: &Point,
         template: &Template,
+        rng: &mut RandomNumberGenerator,
+        level: usize,
         commands: &mut CommandBuffer
-    ) {
+    ) -> Entity {
         let entity = commands.push(
             (
                 pt.clone(),
@@ -79,40 +451,135 @@ impl Templates {
                 Name(template.name.clone()),
             )
         );
+        if let Some(description) = &template.description {
+            commands.add_component(entity, Description(description.clone()));
+        }
 
         match template.entity_type {
             EntityType::Item => {
                 commands.add_component(entity, Item{});
+                if let Some(slot) = template.slot {
+                    commands.add_component(entity, Equippable(slot));
+                }
+                if template.stacks.unwrap_or(false) {
+                    commands.add_component(entity, Stackable{});
+                    commands.add_component(entity, Quantity(1));
+                }
+                if let Some(range) = template.range {
+                    commands.add_component(entity, Ranged(range));
+                }
+                if let Some(charges) = template.charges {
+                    commands.add_component(entity, Charges(charges));
+                }
+                // equipment can come out enchanted or cursed, more strongly deeper down
+                if template.slot.is_some() || template.base_damage.is_some() {
+                    let bonus = roll_enchantment(rng, level);
+                    if bonus != 0 {
+                        commands.add_component(entity, Enchantment(bonus));
+                    }
+                    if bonus < 0 {
+                        commands.add_component(entity, Cursed);
+                    }
+                }
+                if let Some(gold) = template.gold {
+                    commands.add_component(entity, Gold(rng.range(gold / 2, gold) + 1));
+                }
             }
             EntityType::Enemy => {
                 commands.add_component(entity, Enemy{});
                 commands.add_component(entity, FieldOfView::new(6));
-                commands.add_component(entity, ChasingPlayer{});
+                if let Some(spells) = &template.spells {
+                    commands.add_component(entity, Spellcaster {
+                        spells: spells.iter().map(|s| Spell {
+                            kind: s.kind.clone(),
+                            range: s.range,
+                            power: s.power,
+                            cooldown: s.cooldown,
+                            ready_in: 0
+                        }).collect(),
+                        keep_distance: template.keep_distance.unwrap_or(0)
+                    });
+                } else {
+                    match template.ai.unwrap_or(Behaviour::Chase) {
+                        Behaviour::Chase => commands.add_component(entity, ChasingPlayer{}),
+                        Behaviour::Wander => commands.add_component(entity, MovingRandomly{}),
+                        Behaviour::Stationary => commands.add_component(entity, Stationary{}),
+                        Behaviour::Coward => commands.add_component(entity, Cowardly{}),
+                        Behaviour::Patrol => commands.add_component(entity, Patrolling {
+                            direction: Point::new(1, 0)
+                        }),
+                    }
+                }
+                if let Some(threshold) = template.flee_threshold {
+                    commands.add_component(entity, FleesWhenWounded { threshold });
+                }
                 commands.add_component(entity, Health{
                     current: template.hp.unwrap(),
                     max: template.hp.unwrap()
                 });
+                // tougher and harder hitting monsters are worth more experience
+                let experience = template.hp.unwrap() + template.base_damage.unwrap_or(0) * 2;
+                commands.add_component(entity, GrantsExperience(experience));
+                if template.loot.is_some() || template.corpse.is_some() {
+                    commands.add_component(entity, DropsLoot(template.name.clone()));
+                }
+                commands.add_component(
+                    entity,
+                    Faction(template.faction.clone().unwrap_or_else(|| "Monsters".to_string()))
+                );
+                commands.add_component(entity, Perception(template.perception.unwrap_or(2)));
+                if let Some(interval) = template.regen {
+                    commands.add_component(entity, Regeneration::new(interval));
+                }
+                if rng.range(0, 100) < template.sleep_chance.unwrap_or(0) {
+                    commands.add_component(entity, Asleep{});
+                }
+                if template.unique.unwrap_or(false) {
+                    // phases are entered from the highest health threshold down
+                    let mut phases = template.phases.clone().unwrap_or_default();
+                    phases.sort_by(|a, b| b.below.partial_cmp(&a.below).unwrap());
+                    commands.add_component(entity, Boss { phases });
+                }
             }
         }
 
+        if let Some(light) = &template.light {
+            commands.add_component(entity, LightSource {
+                radius: light.radius,
+                colour: RGB::named(light.colour)
+            });
+        }
+
         if let Some(effects) = &template.provides {
-            effects.iter().for_each(|(provides, n)| {
-                match provides.as_str() {
-                    "Healing" => commands.add_component(entity, ProvidesHealing { amount: *n }),
-                    "MagicMap" => commands.add_component(entity, ProvidesDungeonMap{}),
-                    _ => println!("Warning: unknown effect: {}", provides)
-                }
-            })
+            commands.add_component(entity, Provides(effects.clone()));
         }
         
         if let Some(damage) = &template.base_damage {
             println!("Spawning {} with base damage {}", template.name, damage);
             commands.add_component(entity, Damage(*damage));
             if template.entity_type == EntityType::Item {
-                commands.add_component(entity, Weapon{})
+                commands.add_component(entity, Weapon{});
+                if template.slot.is_none() {
+                    commands.add_component(entity, Equippable(EquipmentSlot::MainHand));
+                }
             }
         } else {
             println!("Spawning {} without damage", template.name);       
         }
+
+        entity
     }
-}
\ No newline at end of file
+}
+
+// the bonus rolled for a new piece of equipment, negative when it is cursed
+fn roll_enchantment(rng: &mut RandomNumberGenerator, level: usize) -> i32 {
+    let roll = rng.range(0, 100);
+    let size = if rng.range(0, 3) < level as i32 { 2 } else { 1 };
+    if roll < CURSE_CHANCE {
+        -size
+    } else if roll < CURSE_CHANCE + ENCHANT_CHANCE + ENCHANT_CHANCE_PER_LEVEL * level as i32 {
+        size
+    } else {
+        0
+    }
+}
@@ -0,0 +1,70 @@
+use crate::prelude::*;
+
+// a boss worn down past a phase threshold acts on it, possibly several at once
+// after a heavy blow
+#[system]
+#[write_component(Boss)]
+#[write_component(Health)]
+#[write_component(Damage)]
+#[read_component(Point)]
+#[read_component(Player)]
+pub fn boss_phases(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] templates: &Templates,
+    #[resource] log: &mut GameLog
+) {
+    let mut rng = RandomNumberGenerator::new();
+    let depth = <&Player>::query()
+        .iter(ecs)
+        .next()
+        .map(|player| player.map_level as usize)
+        .unwrap_or(0);
+    let mut occupied: Vec<Point> = <&Point>::query()
+        .filter(component::<Health>())
+        .iter(ecs)
+        .copied()
+        .collect();
+
+    let mut summons = Vec::<(Entity, Point, String, i32)>::new();
+    <(Entity, &mut Boss, &mut Health, Option<&mut Damage>, &Point)>::query()
+        .iter_mut(ecs)
+        .filter(|(_, _, health, _, _)| health.current > 0)
+        .for_each(|(entity, boss, health, mut damage, pos)| {
+            while let Some(phase) = boss.phases.first()
+                && health.current as f32 <= health.max as f32 * phase.below
+            {
+                let phase = boss.phases.remove(0);
+                log.add(LogKind::Attack, phase.message);
+                match phase.action {
+                    PhaseAction::Summon(name, count) => summons.push((*entity, *pos, name, count)),
+                    PhaseAction::Enrage(bonus) => {
+                        if let Some(damage) = damage.as_mut() {
+                            damage.0 += bonus;
+                        }
+                    }
+                    PhaseAction::Heal(amount) => {
+                        health.current = i32::min(health.max, health.current + amount);
+                    }
+                }
+            }
+        });
+
+    for (boss, pos, name, count) in summons {
+        let mut spots: Vec<Point> = Rect::with_size(pos.x - 2, pos.y - 2, 5, 5)
+            .point_set()
+            .into_iter()
+            .filter(|p| map.can_enter_tile(*p) && !occupied.contains(p))
+            .collect();
+
+        for _ in 0..count {
+            if spots.is_empty() {
+                break;
+            }
+            let spot = spots.remove(rng.random_slice_index(&spots).unwrap());
+            occupied.push(spot);
+            templates.spawn_minion(&spot, &name, boss, &mut rng, depth, commands);
+        }
+    }
+}
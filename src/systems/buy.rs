@@ -0,0 +1,83 @@
+use crate::prelude::*;
+
+#[system]
+#[read_component(WantsToBuy)]
+#[read_component(ForSale)]
+#[write_component(Wallet)]
+#[read_component(Item)]
+#[read_component(Carried)]
+#[read_component(Name)]
+#[read_component(Quantity)]
+#[read_component(Charges)]
+#[read_component(Stackable)]
+#[read_component(Enchantment)]
+#[read_component(Appraised)]
+#[read_component(Cursed)]
+pub fn buy_items(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] log: &mut GameLog,
+    #[resource] identification: &Identification
+) {
+    let purchases: Vec<(Entity, WantsToBuy)> = <(Entity, &WantsToBuy)>::query()
+        .iter(ecs)
+        .map(|(message, buy)| (*message, *buy))
+        .collect();
+
+    purchases.iter().for_each(|(message, buy)| {
+        commands.remove(*message);
+
+        let (price, name, quantity, stackable) = match ecs.entry_ref(buy.item) {
+            Ok(item) => match item.get_component::<ForSale>() {
+                Ok(sale) => (
+                    sale.price,
+                    item.get_component::<Name>().map(|n| n.0.clone()).unwrap_or_default(),
+                    item.get_component::<Quantity>().map(|q| q.0).unwrap_or(1),
+                    item.get_component::<Stackable>().is_ok()
+                ),
+                Err(_) => return
+            },
+            Err(_) => return
+        };
+        let display_name = identification.name_of(&name);
+
+        // bought items go into a matching stack, or need a free slot of their own
+        let stack = <(Entity, &Carried, &Name, &Quantity)>::query()
+            .filter(component::<Stackable>())
+            .iter(ecs)
+            .find(|(_, carried, stack_name, _)| stackable && carried.0 == buy.buyer && stack_name.0 == name)
+            .map(|(entity, _, _, stack_quantity)| (*entity, stack_quantity.0));
+        if stack.is_none() && carried_items(ecs, buy.buyer, identification).len() >= INVENTORY_CAPACITY {
+            log.add(LogKind::Item, "Your backpack is full.");
+            return;
+        }
+
+        let paid = match ecs.entry_mut(buy.buyer) {
+            Ok(mut buyer) => match buyer.get_component_mut::<Wallet>() {
+                Ok(wallet) if wallet.0 >= price => {
+                    wallet.0 -= price;
+                    true
+                },
+                _ => false
+            },
+            Err(_) => false
+        };
+        if !paid {
+            log.add(LogKind::Item, "You cannot afford that.");
+            return;
+        }
+
+        match stack {
+            Some((stack, count)) => {
+                commands.add_component(stack, Quantity(count + quantity));
+                commands.remove(buy.item);
+            },
+            None => {
+                commands.remove_component::<Point>(buy.item);
+                commands.remove_component::<ForSale>(buy.item);
+                commands.add_component(buy.item, Carried(buy.buyer));
+            }
+        }
+        log.add(LogKind::Item, format!("You buy the {} for {} gold.", display_name, price));
+    });
+}
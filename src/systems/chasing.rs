@@ -1,4 +1,12 @@
 use crate::prelude::*;
+use super::fleeing::is_fleeing;
+
+use std::collections::HashSet;
+
+// how many turns a monster spends hunting for a player it has lost sight of
+const SEARCH_TURNS: i32 = 10;
+// how far from the last sighting a searching monster will wander
+const SEARCH_RADIUS: f32 = 3.0;
 
 #[system]
 #[read_component(Point)]
@@ -6,51 +14,119 @@ use crate::prelude::*;
 #[read_component(FieldOfView)]
 #[read_component(Health)]
 #[read_component(Player)]
+#[read_component(FleesWhenWounded)]
+#[read_component(PlayerMemory)]
+#[read_component(Asleep)]
+#[read_component(Confused)]
+#[read_component(Faction)]
+#[read_component(Pack)]
 pub fn chasing(
     #[resource] map: &Map,
+    #[resource] flow_fields: &mut FlowFields,
+    #[resource] factions: &Factions,
     ecs: &SubWorld,
     commands: &mut CommandBuffer
 ) {
-    let mut movers = <(Entity, &Point, &ChasingPlayer, &FieldOfView)>::query();
+    let mut movers = <(Entity, &Point, &ChasingPlayer, &FieldOfView, &Faction, Option<&PlayerMemory>, Option<&Pack>)>::query()
+        .filter(!component::<Asleep>() & !component::<Confused>());
+    let mut pack_members = <(&Pack, &FieldOfView, &Faction)>::query().filter(!component::<Asleep>() & !component::<Confused>());
     let mut positions = <(Entity, &Point, &Health)>::query();
-    let mut player = <(&Point, &Player)>::query();
-
-    let player_pos = player.iter(ecs).next().unwrap().0;
-    let player_idx = map_idx(player_pos.x, player_pos.y);
-
-    let search_targets = vec![player_idx];
-    let dijkstra_map = DijkstraMap::new(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
-        &search_targets,
-        map,
-        1024.0
-    );
-
-    movers.iter(ecs).for_each(|(entity, pos, _, fov)| {
-        if !fov.visible_tiles.contains(player_pos) {
-            // entity can't see player, no need to move
+    let mut targets = <(Entity, &Point, &Faction)>::query().filter(component::<Health>());
+    let mut player = <(Entity, &Point)>::query().filter(component::<Player>());
+    let mut rng = RandomNumberGenerator::new();
+
+    let (player, player_pos) = player.iter(ecs).next().unwrap();
+    flow_fields.ensure(FlowField::TowardPlayer, map, ecs);
+    let dijkstra_map = flow_fields.get(FlowField::TowardPlayer);
+    let player_faction = ecs.entry_ref(*player).unwrap().get_component::<Faction>().unwrap().clone();
+
+    // packs where any member can see the player all know where the player is
+    let spotted: HashSet<usize> = pack_members
+        .iter(ecs)
+        .filter(|(_, fov, faction)| {
+            fov.visible_tiles.contains(player_pos) && factions.is_hostile(faction, &player_faction)
+        })
+        .map(|(pack, _, _)| pack.0)
+        .collect();
+    let occupied: HashSet<Point> = positions.iter(ecs).map(|(_, pos, _)| *pos).collect();
+    let mut claimed = HashSet::new();
+
+    movers.iter(ecs).for_each(|(entity, pos, _, fov, faction, memory, pack)| {
+        if is_fleeing(ecs, factions, *entity, *player) {
             return;
         }
 
+        // go for the closest enemy in sight, which is not always the player
+        let nearest = targets
+            .iter(ecs)
+            .filter(|(target, target_pos, target_faction)| {
+                *target != entity
+                    && fov.visible_tiles.contains(target_pos)
+                    && factions.is_hostile(faction, target_faction)
+            })
+            .map(|(target, target_pos, _)| {
+                (*target, *target_pos, DistanceAlg::Pythagoras.distance2d(*pos, *target_pos))
+            })
+            .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap())
+            .or_else(|| {
+                pack.filter(|pack| spotted.contains(&pack.0))
+                    .map(|_| (*player, *player_pos, DistanceAlg::Pythagoras.distance2d(*pos, *player_pos)))
+            });
+
         let idx = map_idx(pos.x, pos.y);
-        if let Some(destination) = DijkstraMap::find_lowest_exit(&dijkstra_map, idx, map) {
-            // figure out the distance between pos and player
-            let distance = DistanceAlg::Pythagoras.distance2d(*pos, *player_pos);
+        let destination = if let Some((target, target_pos, distance)) = nearest {
+            if target == *player {
+                commands.add_component(*entity, PlayerMemory {
+                    last_seen: target_pos,
+                    turns_since_seen: 0
+                });
+            }
+            // stop wandering about if it had given up on an earlier search
+            commands.remove_component::<MovingRandomly>(*entity);
+
             // if the destination is gt 1.2 tiles, go to the nearest exist
-            let destination = if distance > 1.2 {
-                map.index_to_point2d(destination)
+            if distance <= 1.2 {
+                Some(target_pos)
+            } else if let Some(step) = pack
+                .filter(|_| target == *player)
+                .and_then(|_| surround(map, &occupied, &mut claimed, *pos, *player_pos))
+            {
+                Some(step)
+            } else if target == *player {
+                DijkstraMap::find_lowest_exit(dijkstra_map, idx, map)
+                    .map(|exit| map.index_to_point2d(exit))
             } else {
-                *player_pos
-            };
+                let path = a_star_search(idx, map.point2d_to_index(target_pos), map);
+                path.steps
+                    .get(1)
+                    .filter(|_| path.success)
+                    .map(|step| map.index_to_point2d(*step))
+            }
+        } else if let Some(memory) = memory {
+            if memory.turns_since_seen >= SEARCH_TURNS {
+                // give up the hunt and go back to wandering
+                commands.remove_component::<PlayerMemory>(*entity);
+                commands.add_component(*entity, MovingRandomly{});
+                return;
+            }
+            commands.add_component(*entity, PlayerMemory {
+                last_seen: memory.last_seen,
+                turns_since_seen: memory.turns_since_seen + 1
+            });
+            search(map, &mut rng, *pos, memory.last_seen)
+        } else {
+            // entity can't see anything to fight, no need to move
+            None
+        };
 
+        if let Some(destination) = destination {
             let mut attacked = false;
 
             positions
                 .iter(ecs)
                 .filter(|(_, target_pos, _)| **target_pos == destination)
                 .for_each(|(target, _, _)| {
-                    if ecs.entry_ref(*target).unwrap().get_component::<Player>().is_ok() {
+                    if factions.entity_is_hostile(ecs, *entity, *target) {
                         commands.push(((), WantsToAttack {
                             attacker: *entity,
                             target: *target
@@ -68,3 +144,60 @@ pub fn chasing(
         }
     });
 }
+
+// pack members each pick a different free tile next to the player, so they
+// come at the player from several sides instead of queueing in a corridor
+fn surround(
+    map: &Map,
+    occupied: &HashSet<Point>,
+    claimed: &mut HashSet<Point>,
+    pos: Point,
+    player_pos: Point
+) -> Option<Point> {
+    let slot = [Point::new(-1, 0), Point::new(1, 0), Point::new(0, -1), Point::new(0, 1)]
+        .iter()
+        .map(|delta| player_pos + *delta)
+        .filter(|slot| map.can_enter_tile(*slot) && !occupied.contains(slot) && !claimed.contains(slot))
+        .min_by(|a, b| {
+            DistanceAlg::Pythagoras.distance2d(pos, *a)
+                .partial_cmp(&DistanceAlg::Pythagoras.distance2d(pos, *b))
+                .unwrap()
+        })?;
+    claimed.insert(slot);
+
+    let path = a_star_search(map.point2d_to_index(pos), map.point2d_to_index(slot), map);
+    path.steps
+        .get(1)
+        .filter(|_| path.success)
+        .map(|step| map.index_to_point2d(*step))
+}
+
+// head for the last place the player was seen, then poke around near it
+fn search(
+    map: &Map,
+    rng: &mut RandomNumberGenerator,
+    pos: Point,
+    last_seen: Point
+) -> Option<Point> {
+    if pos != last_seen {
+        let path = a_star_search(map.point2d_to_index(pos), map.point2d_to_index(last_seen), map);
+        if path.success && path.steps.len() > 1 {
+            return Some(map.index_to_point2d(path.steps[1]));
+        }
+    }
+
+    let delta = match rng.range(0, 4) {
+        0 => Point::new(-1, 0),
+        1 => Point::new(1, 0),
+        2 => Point::new(0, -1),
+        _ => Point::new(0, 1),
+    };
+    let destination = pos + delta;
+    if map.can_enter_tile(destination)
+        && DistanceAlg::Pythagoras.distance2d(destination, last_seen) <= SEARCH_RADIUS
+    {
+        Some(destination)
+    } else {
+        None
+    }
+}
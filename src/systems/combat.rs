@@ -1,20 +1,48 @@
 use crate::prelude::*;
 
+const FIGHT_VOLUME: i32 = 6;
+const SNEAK_ATTACK_MULTIPLIER: i32 = 2;
+
 #[system]
 #[read_component(WantsToAttack)]
+#[read_component(InflictDamage)]
 #[read_component(Player)]
 #[write_component(Health)]
 #[read_component(Damage)]
-#[read_component(Carried)]
-pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+#[read_component(Equipped)]
+#[read_component(Enchantment)]
+#[read_component(GrantsExperience)]
+#[write_component(Experience)]
+#[read_component(DropsLoot)]
+#[read_component(Point)]
+#[read_component(Name)]
+#[read_component(Asleep)]
+#[read_component(Hunger)]
+pub fn combat(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] templates: &Templates,
+    #[resource] log: &mut GameLog
+) {
     let mut attackers = <(Entity, &WantsToAttack)>::query();
+    let mut rng = RandomNumberGenerator::new();
+
+    let depth = <&Player>::query()
+        .iter(ecs)
+        .next()
+        .map(|player| player.map_level as usize)
+        .unwrap_or(0);
 
-    let targets: Vec<(Entity, Entity, Entity)> = attackers
+    // melee attacks roll their damage below, spells and the like bring their own
+    let mut targets: Vec<(Entity, Entity, Entity, Option<i32>)> = attackers
         .iter(ecs)
-        .map(|(entity, attack)| (*entity, attack.attacker, attack.target))
+        .map(|(entity, attack)| (*entity, attack.attacker, attack.target, None))
         .collect();
+    <(Entity, &InflictDamage)>::query()
+        .iter(ecs)
+        .for_each(|(entity, damage)| targets.push((*entity, damage.source, damage.target, Some(damage.amount))));
 
-    targets.iter().for_each(|(message, attacker, target)| {
+    targets.iter().for_each(|(message, attacker, target, fixed_damage)| {
         let is_player = ecs
             .entry_ref(*target)
             .unwrap()
@@ -31,24 +59,122 @@ pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
             0
         };
 
-        let weapon_damage: i32 =  <(&Carried, &Damage)>::query()
+        // only equipped items count, not everything in the backpack
+        let weapon_damage: i32 =  <(&Equipped, &Damage, Option<&Enchantment>)>::query()
             .iter(ecs)
-            .filter(|(carried, _)| carried.0 == *attacker)
-            .map(|(_, dmg)| dmg.0)
+            .filter(|(equipped, _, _)| equipped.owner == *attacker)
+            .map(|(_, dmg, enchantment)| i32::max(0, dmg.0 + enchantment.map(|e| e.0).unwrap_or(0)))
             .sum();
 
-        let final_damage = base_damage + weapon_damage;
+        // a hungry attacker hits softer, though never for nothing
+        let mut melee_damage = base_damage + weapon_damage;
+        if let Ok(entry) = ecs.entry_ref(*attacker)
+            && let Ok(hunger) = entry.get_component::<Hunger>()
+            && hunger.state().damage_penalty() > 0
+        {
+            melee_damage = i32::max(1, melee_damage - hunger.state().damage_penalty());
+        }
+
+        let mut final_damage = fixed_damage.unwrap_or(melee_damage);
+        let attacker_name = describe(ecs, *attacker);
+        let target_name = describe(ecs, *target);
 
+        if let Ok(entry) = ecs.entry_ref(*target) {
+            // a target killed by an earlier blow this turn is already queued for removal
+            let alive = entry.get_component::<Health>().is_ok_and(|h| h.current > 0);
+            if alive && entry.get_component::<Asleep>().is_ok() {
+                // sleeping targets take extra damage, and are woken by the blow
+                final_damage *= SNEAK_ATTACK_MULTIPLIER;
+                commands.remove_component::<Asleep>(*target);
+                if let Some(name) = &target_name {
+                    log.add(LogKind::Attack, format!("The {} is caught sleeping!", name));
+                }
+            }
+            if let Ok(pos) = entry.get_component::<Point>() {
+                commands.push(((), Noise {
+                    pos: *pos,
+                    volume: FIGHT_VOLUME
+                }));
+            }
+        }
+
+        let mut killed = false;
         if let Ok(health) = ecs
             .entry_mut(*target)
             .unwrap()
             .get_component_mut::<Health>()
         {
+            // only the blow that takes the target below 1 hp counts as the kill
+            let was_alive = health.current > 0;
             health.current -= final_damage;
             if health.current < 1 && !is_player {
+                killed = was_alive;
                 commands.remove(*target);
             }
+
+            if was_alive {
+                log_attack(log, &attacker_name, &target_name, final_damage);
+                if health.current < 1 {
+                    log_death(log, &attacker_name, &target_name);
+                }
+            }
+        }
+
+        if killed
+            && let Ok(entry) = ecs.entry_ref(*target)
+            && let (Ok(loot), Ok(pos)) = (entry.get_component::<DropsLoot>(), entry.get_component::<Point>())
+        {
+            templates.drop_loot(pos, &loot.0, depth, &mut rng, commands);
+        }
+
+        let reward = ecs
+            .entry_ref(*target)
+            .ok()
+            .and_then(|e| e.get_component::<GrantsExperience>().ok().map(|xp| xp.0))
+            .filter(|_| killed);
+
+        if let Some(reward) = reward
+            && let Ok(mut attacker) = ecs.entry_mut(*attacker)
+            && let Ok(xp) = attacker.get_component_mut::<Experience>()
+        {
+            xp.current += reward;
         }
         commands.remove(*message);
     });
 }
+
+// the player has no Name, so None means the entity is addressed as "you"
+fn describe(ecs: &SubWorld, entity: Entity) -> Option<String> {
+    ecs.entry_ref(entity)
+        .ok()?
+        .get_component::<Name>()
+        .ok()
+        .map(|name| name.0.clone())
+}
+
+fn log_attack(log: &mut GameLog, attacker: &Option<String>, target: &Option<String>, damage: i32) {
+    match (attacker, target) {
+        (None, Some(target)) => log.add(
+            LogKind::Attack,
+            format!("You hit the {} for {} damage.", target, damage)
+        ),
+        (Some(attacker), None) => log.add(
+            LogKind::Hurt,
+            format!("The {} hits you for {} damage.", attacker, damage)
+        ),
+        (Some(attacker), Some(target)) => log.add(
+            LogKind::Attack,
+            format!("The {} hits the {} for {} damage.", attacker, target, damage)
+        ),
+        (None, None) => {}
+    }
+}
+
+fn log_death(log: &mut GameLog, attacker: &Option<String>, target: &Option<String>) {
+    match (attacker, target) {
+        (None, Some(target)) => log.add(LogKind::Kill, format!("You kill the {}.", target)),
+        (Some(attacker), None) => log.add(LogKind::Hurt, format!("You are slain by the {}.", attacker)),
+        (Some(_), Some(target)) => log.add(LogKind::Kill, format!("The {} dies.", target)),
+        (None, None) => {}
+    }
+}
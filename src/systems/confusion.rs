@@ -0,0 +1,59 @@
+use crate::prelude::*;
+
+// confused monsters stumble in a random direction, striking whatever is in
+// the way whichever side it is on
+#[system]
+#[read_component(Point)]
+#[read_component(Confused)]
+#[read_component(Health)]
+#[read_component(Name)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+pub fn confusion(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] log: &mut GameLog
+) {
+    let mut rng = RandomNumberGenerator::new();
+    let mut positions = <(Entity, &Point, &Health)>::query();
+    let player_fov = <&FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+
+    <(Entity, &Point, &Confused, &Name)>::query()
+        .iter(ecs)
+        .for_each(|(entity, pos, confused, name)| {
+            let destination = match rng.range(0, 4) {
+                0 => Point::new(-1, 0),
+                1 => Point::new(1, 0),
+                2 => Point::new(0, -1),
+                _ => Point::new(0, 1),
+            } + *pos;
+
+            let mut blocked = false;
+            positions
+                .iter(ecs)
+                .filter(|(target, target_pos, _)| **target_pos == destination && *target != entity)
+                .for_each(|(target, _, _)| {
+                    commands.push(((), WantsToAttack {
+                        attacker: *entity,
+                        target: *target
+                    }));
+                    blocked = true;
+                });
+            if !blocked {
+                commands.push(((), WantsToMove { entity: *entity, destination }));
+            }
+
+            if confused.turns > 1 {
+                commands.add_component(*entity, Confused { turns: confused.turns - 1 });
+            } else {
+                commands.remove_component::<Confused>(*entity);
+                if player_fov.visible_tiles.contains(pos) {
+                    log.add(LogKind::Info, format!("The {} is no longer confused.", name.0));
+                }
+            }
+        });
+}
@@ -0,0 +1,42 @@
+use crate::prelude::*;
+
+#[system]
+#[read_component(WantsToDrop)]
+#[read_component(Point)]
+#[read_component(Name)]
+#[read_component(Equipped)]
+#[read_component(Enchantment)]
+#[read_component(Appraised)]
+#[read_component(Cursed)]
+pub fn drop_items(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] log: &mut GameLog,
+    #[resource] identification: &Identification
+) {
+    <(Entity, &WantsToDrop)>::query()
+        .iter(ecs)
+        .for_each(|(message, drop)| {
+            commands.remove(*message);
+            if let Ok(item) = ecs.entry_ref(drop.item)
+                && item.get_component::<Equipped>().is_ok()
+                && item.get_component::<Cursed>().is_ok()
+            {
+                log.add(
+                    LogKind::Hurt,
+                    format!("You cannot let go of the {}!", item_name(ecs, drop.item, identification))
+                );
+                return;
+            }
+            if let Ok(owner) = ecs.entry_ref(drop.entity)
+                && let Ok(pos) = owner.get_component::<Point>()
+            {
+                // dropped equipment is taken off first
+                commands.remove_component::<Carried>(drop.item);
+                commands.remove_component::<Equipped>(drop.item);
+                commands.add_component(drop.item, *pos);
+
+                log.add(LogKind::Item, format!("You drop the {}.", item_name(ecs, drop.item, identification)));
+            }
+        });
+}
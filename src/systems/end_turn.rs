@@ -5,19 +5,21 @@ use crate::prelude::*;
 #[read_component(Point)]
 #[read_component(Player)]
 #[read_component(AmuletOfYala)]
+#[read_component(Carried)]
+#[read_component(Boss)]
 pub fn end_turn(
     ecs: &mut SubWorld,
     #[resource] turn_state: &mut TurnState,
     #[resource] map: &Map,
 ) {
     let mut player_hp = <(&Health, &Point)>::query().filter(component::<Player>());
-    let mut amulet = <&Point>::query().filter(component::<AmuletOfYala>());
-
-    let amulet_default = Point::new(-1, -1);
-    let amulet_pos = amulet
+    // the quest is only won once the amulet is taken and its guardian is dead
+    let has_amulet = <&Carried>::query()
+        .filter(component::<AmuletOfYala>())
         .iter(ecs)
         .next()
-        .unwrap_or(&(amulet_default));
+        .is_some();
+    let boss_alive = <&Boss>::query().iter(ecs).next().is_some();
 
     let current_state = turn_state.clone();
     let mut new_state = match current_state {
@@ -31,7 +33,7 @@ pub fn end_turn(
         if hp.current < 1 {
             new_state = TurnState::GameOver;
         }
-        if pos == amulet_pos {
+        if has_amulet && !boss_alive {
             new_state = TurnState::Victory;
         }
         let idx = map.point2d_to_index(*pos);
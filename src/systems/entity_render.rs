@@ -7,8 +7,9 @@ use crate::prelude::*;
 #[read_component(Render)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
+#[read_component(Revealed)]
 pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
-    let mut renderables = <(&Point, &Render)>::query();
+    let mut renderables = <(&Point, &Render, Option<&Revealed>)>::query();
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
 
     let mut draw_batch = DrawBatch::new();
@@ -19,8 +20,8 @@ pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
     // match any entity with Point and Render
     renderables
         .iter(ecs)
-        .filter(|(pos, _)| player_fov.visible_tiles.contains(pos))
-        .for_each(|(pos, render)| {
+        .filter(|(pos, _, revealed)| player_fov.visible_tiles.contains(pos) || revealed.is_some())
+        .for_each(|(pos, render, _)| {
             draw_batch.set(*pos - offset, render.colour, render.glyph);
         });
 
@@ -0,0 +1,101 @@
+use crate::prelude::*;
+
+use std::collections::HashMap;
+
+#[system]
+#[read_component(WantsToEquip)]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(Name)]
+#[read_component(Enchantment)]
+#[read_component(Appraised)]
+#[read_component(Cursed)]
+pub fn equip(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] log: &mut GameLog,
+    #[resource] identification: &Identification
+) {
+    let requests: Vec<(Entity, WantsToEquip)> = <(Entity, &WantsToEquip)>::query()
+        .iter(ecs)
+        .map(|(entity, want)| (*entity, *want))
+        .collect();
+
+    // kept up to date as requests are handled, so two items picked up in the
+    // same turn cannot both take one slot
+    let mut equipped: HashMap<Entity, Equipped> = <(Entity, &Equipped)>::query()
+        .iter(ecs)
+        .map(|(item, equipped)| (*item, *equipped))
+        .collect();
+
+    let name_of = |item: Entity| item_name(ecs, item, identification);
+    let is_cursed = |item: Entity| {
+        ecs.entry_ref(item)
+            .is_ok_and(|e| e.get_component::<Cursed>().is_ok())
+    };
+
+    requests.iter().for_each(|(message, want)| {
+        commands.remove(*message);
+        if equipped.contains_key(&want.item) {
+            if is_cursed(want.item) {
+                log.add(LogKind::Hurt, format!("You cannot let go of the {}!", name_of(want.item)));
+                return;
+            }
+            equipped.remove(&want.item);
+            commands.remove_component::<Equipped>(want.item);
+            log.add(LogKind::Item, format!("You put away the {}.", name_of(want.item)));
+        } else if let Ok(item) = ecs.entry_ref(want.item)
+            && let Ok(equippable) = item.get_component::<Equippable>()
+        {
+            let slot = equippable.0;
+
+            // whatever is already in the slot goes back in the backpack, unless it is stuck there
+            let replaced: Vec<Entity> = equipped
+                .iter()
+                .filter(|(_, e)| e.owner == want.entity && e.slot == slot)
+                .map(|(old, _)| *old)
+                .collect();
+            if let Some(stuck) = replaced.iter().find(|old| is_cursed(**old)) {
+                log.add(LogKind::Hurt, format!("You cannot let go of the {}!", name_of(*stuck)));
+                return;
+            }
+            replaced.iter().for_each(|old| {
+                equipped.remove(old);
+                commands.remove_component::<Equipped>(*old);
+                log.add(LogKind::Item, format!("You put away the {}.", name_of(*old)));
+            });
+
+            let equipment = Equipped {
+                owner: want.entity,
+                slot
+            };
+            equipped.insert(want.item, equipment);
+            commands.add_component(want.item, equipment);
+
+            log.add(
+                LogKind::Item,
+                format!("You equip the {} ({}).", name_of(want.item), slot.describe())
+            );
+
+            // wielding an item is enough to tell how good it is
+            if item.get_component::<Appraised>().is_err() {
+                commands.add_component(want.item, Appraised);
+                match item.get_component::<Enchantment>() {
+                    Ok(enchantment) if is_cursed(want.item) => log.add(
+                        LogKind::Hurt,
+                        format!("It binds itself to you, it is cursed ({:+})!", enchantment.0)
+                    ),
+                    Ok(enchantment) if enchantment.0 < 0 => log.add(
+                        LogKind::Item,
+                        format!("It feels poorly made ({:+}).", enchantment.0)
+                    ),
+                    Ok(enchantment) => log.add(
+                        LogKind::Item,
+                        format!("It feels well made ({:+}).", enchantment.0)
+                    ),
+                    Err(_) => {}
+                }
+            }
+        }
+    });
+}
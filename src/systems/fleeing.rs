@@ -0,0 +1,101 @@
+use crate::prelude::*;
+
+use std::collections::HashSet;
+
+// cowards and badly wounded monsters run from a hostile player they can see
+#[system]
+#[read_component(Point)]
+#[read_component(Cowardly)]
+#[read_component(FleesWhenWounded)]
+#[read_component(FieldOfView)]
+#[read_component(Health)]
+#[read_component(Player)]
+#[read_component(Asleep)]
+#[read_component(Confused)]
+#[read_component(Faction)]
+pub fn fleeing(
+    #[resource] map: &Map,
+    #[resource] flow_fields: &mut FlowFields,
+    #[resource] factions: &Factions,
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer
+) {
+    let mut movers = <(Entity, &Point, &FieldOfView, &Health, Option<&Cowardly>, Option<&FleesWhenWounded>)>::query()
+        .filter(!component::<Asleep>() & !component::<Confused>());
+    let mut positions = <&Point>::query().filter(component::<Health>());
+    let mut player = <(Entity, &Point)>::query().filter(component::<Player>());
+
+    let (player, player_pos) = player.iter(ecs).next().unwrap();
+
+    let runners: Vec<(Entity, Point, bool)> = movers
+        .iter(ecs)
+        .filter(|(entity, _, fov, _, _, _)| {
+            fov.visible_tiles.contains(player_pos) && factions.entity_is_hostile(ecs, **entity, *player)
+        })
+        .filter(|(_, _, _, health, cowardly, flees)| {
+            cowardly.is_some() || flees.is_some_and(|f| f.is_wounded(health))
+        })
+        .map(|(entity, pos, _, _, cowardly, _)| (*entity, *pos, cowardly.is_some()))
+        .collect();
+
+    if runners.is_empty() {
+        // don't pay for the flee map when nobody is running away
+        return;
+    }
+
+    flow_fields.ensure(FlowField::AwayFromPlayer, map, ecs);
+    let chase_map = flow_fields.get(FlowField::TowardPlayer);
+    let flee_map = flow_fields.get(FlowField::AwayFromPlayer);
+    let occupied: HashSet<Point> = positions.iter(ecs).copied().collect();
+
+    runners.iter().for_each(|(entity, pos, cowardly)| {
+        let idx = map_idx(pos.x, pos.y);
+
+        let escape = DijkstraMap::find_lowest_exit(flee_map, idx, map)
+            .filter(|exit| flee_map.map[*exit] < flee_map.map[idx])
+            .map(|exit| map.index_to_point2d(exit))
+            .filter(|destination| !occupied.contains(destination));
+
+        if let Some(destination) = escape {
+            commands.push(((), WantsToMove {
+                entity: *entity,
+                destination
+            }));
+        } else if DistanceAlg::Pythagoras.distance2d(*pos, *player_pos) < 1.2 {
+            // cornered, so fight back
+            commands.push(((), WantsToAttack {
+                attacker: *entity,
+                target: *player
+            }));
+        } else if !cowardly {
+            // nowhere left to run, go back to chasing the player
+            let destination = DijkstraMap::find_lowest_exit(chase_map, idx, map)
+                .map(|exit| map.index_to_point2d(exit))
+                .filter(|destination| !occupied.contains(destination));
+
+            if let Some(destination) = destination {
+                commands.push(((), WantsToMove {
+                    entity: *entity,
+                    destination
+                }));
+            }
+        }
+    });
+}
+
+// wounded monsters that can see a hostile player are moved by the fleeing system,
+// so the other behaviours leave them alone. Allies have nothing to run from
+pub fn is_fleeing(ecs: &SubWorld, factions: &Factions, entity: Entity, player: Entity) -> bool {
+    if factions.entity_is_hostile(ecs, entity, player)
+        && let Ok(player_entry) = ecs.entry_ref(player)
+        && let Ok(player_pos) = player_entry.get_component::<Point>()
+        && let Ok(entry) = ecs.entry_ref(entity)
+        && let Ok(flees) = entry.get_component::<FleesWhenWounded>()
+        && let Ok(health) = entry.get_component::<Health>()
+        && let Ok(fov) = entry.get_component::<FieldOfView>()
+    {
+        flees.is_wounded(health) && fov.visible_tiles.contains(player_pos)
+    } else {
+        false
+    }
+}
@@ -1,5 +1,8 @@
 use crate::prelude::*;
 
+// tiles darker than this cannot be made out, unless they are right next to you
+const MIN_VISIBLE_LIGHT: f32 = 0.2;
+
 #[system]
 #[read_component(Point)]
 #[write_component(FieldOfView)]
@@ -14,7 +17,14 @@ pub fn fov(
         .iter_mut(ecs)
         .filter(|(_, fov)| fov.is_dirty)
         .for_each(|(pos, fov)| {
-            fov.visible_tiles = field_of_view_set(*pos, fov.radius, map);
+            fov.visible_tiles = field_of_view_set(*pos, fov.radius, map)
+                .into_iter()
+                .filter(|pt| match map.try_idx(*pt) {
+                    Some(idx) => map.light[idx] >= MIN_VISIBLE_LIGHT ||
+                        DistanceAlg::Pythagoras.distance2d(*pos, *pt) < 1.5,
+                    None => false
+                })
+                .collect();
             fov.is_dirty = false;
         });
 }
@@ -0,0 +1,39 @@
+use crate::prelude::*;
+
+// gold is scooped up as soon as the player steps on it, it takes no turn
+#[system]
+#[read_component(Player)]
+#[read_component(Point)]
+#[read_component(Gold)]
+#[write_component(Wallet)]
+pub fn collect_gold(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] log: &mut GameLog
+) {
+    let (player, player_pos) = match <(Entity, &Point)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(entity, pos)| (*entity, *pos))
+        .next()
+    {
+        Some(player) => player,
+        None => return
+    };
+
+    let piles: Vec<(Entity, i32)> = <(Entity, &Point, &Gold)>::query()
+        .iter(ecs)
+        .filter(|(_, pos, _)| **pos == player_pos)
+        .map(|(entity, _, gold)| (*entity, gold.0))
+        .collect();
+
+    if let Ok(mut entry) = ecs.entry_mut(player)
+        && let Ok(wallet) = entry.get_component_mut::<Wallet>()
+    {
+        piles.iter().for_each(|(pile, amount)| {
+            wallet.0 += amount;
+            commands.remove(*pile);
+            log.add(LogKind::Item, format!("You pick up {} gold.", amount));
+        });
+    }
+}
@@ -5,8 +5,18 @@ use crate::prelude::*;
 #[read_component(Player)]
 #[read_component(Item)]
 #[read_component(Carried)]
+#[read_component(Equipped)]
+#[read_component(Quantity)]
+#[read_component(Charges)]
 #[read_component(Name)]
-pub fn hud(ecs: &SubWorld) {
+#[read_component(Experience)]
+#[read_component(LevelUp)]
+#[read_component(Hunger)]
+#[read_component(Wallet)]
+#[read_component(Enchantment)]
+#[read_component(Appraised)]
+#[read_component(Cursed)]
+pub fn hud(ecs: &SubWorld, #[resource] identification: &Identification) {
     let mut health_query = <&Health>::query().filter(component::<Player>());
 
     let player_health = health_query
@@ -42,20 +52,66 @@ pub fn hud(ecs: &SubWorld) {
 
     draw_batch.print_color_right(
         Point::new(SCREEN_HEIGHT * 2, 1),
-        &format!("Level: {}", map_level),
+        &format!("Dungeon Level: {}", map_level),
         ColorPair::new(YELLOW, BLACK)
     );
 
-    let mut item_query = <(&Item, &Name, &Carried)>::query();
+    if let Some(xp) = <&Experience>::query().filter(component::<Player>()).iter(ecs).next() {
+        draw_batch.print_color_right(
+            Point::new(SCREEN_HEIGHT * 2, 2),
+            format!("Character Level: {} ({} / {} xp)", xp.level, xp.current, xp.next_level),
+            ColorPair::new(CYAN, BLACK)
+        );
+    }
+
+    if let Some(hunger) = <&Hunger>::query().filter(component::<Player>()).iter(ecs).next() {
+        let state = hunger.state();
+        draw_batch.print_color_right(
+            Point::new(SCREEN_HEIGHT * 2, 3),
+            state.describe(),
+            ColorPair::new(state.colour(), BLACK)
+        );
+    }
+
+    if let Some(wallet) = <&Wallet>::query().filter(component::<Player>()).iter(ecs).next() {
+        draw_batch.print_color_right(
+            Point::new(SCREEN_HEIGHT * 2, 4),
+            format!("Gold: {}", wallet.0),
+            ColorPair::new(GOLD, BLACK)
+        );
+    }
+
+    if let Some(level_up) = <&LevelUp>::query().filter(component::<Player>()).iter(ecs).next() {
+        draw_batch.print_color_centered(
+            3,
+            format!(
+                " Level up! You are now level {}. You feel stronger. ",
+                level_up.level
+            ),
+            ColorPair::new(GOLD, BLACK)
+        );
+    }
+
+    // only the first nine items have a number key, the rest are in the inventory screen
     let mut y = 3;
-    item_query
-        .iter(ecs)
-        .filter(|(_, _, carried)| carried.0 == player)
-        .for_each(|(_, name, _)| {
-            draw_batch.print(
-                Point::new(3, y),
-                format!("{} : {}", y - 2, &name.0)
-            );
+    carried_items(ecs, player, identification)
+        .iter()
+        .take(9)
+        .for_each(|(item, name)| {
+            let equipped = ecs
+                .entry_ref(*item)
+                .ok()
+                .and_then(|e| e.get_component::<Equipped>().ok().copied());
+            match equipped {
+                Some(equipped) => draw_batch.print(
+                    Point::new(3, y),
+                    format!("{} : {} ({})", y - 2, name, equipped.slot.describe())
+                ),
+                None => draw_batch.print(
+                    Point::new(3, y),
+                    format!("{} : {}", y - 2, name)
+                )
+            };
             y += 1;
         });
     if y > 3 {
@@ -0,0 +1,34 @@
+use crate::prelude::*;
+
+// turns between each point of health lost while starving
+const STARVATION_INTERVAL: i32 = 10;
+
+#[system]
+#[read_component(Player)]
+#[write_component(Hunger)]
+#[write_component(Health)]
+pub fn hunger(ecs: &mut SubWorld, #[resource] log: &mut GameLog) {
+    <(&mut Hunger, &mut Health)>::query()
+        .filter(component::<Player>())
+        .iter_mut(ecs)
+        .for_each(|(hunger, health)| {
+            let before = hunger.state();
+            hunger.food -= 1;
+            let state = hunger.state();
+
+            if state != before {
+                match state {
+                    HungerState::Hungry => log.add(LogKind::Hurt, "You are getting hungry."),
+                    HungerState::Weak => log.add(LogKind::Hurt, "You feel weak with hunger."),
+                    HungerState::Starving => log.add(LogKind::Hurt, "You are starving!"),
+                    HungerState::Satiated => {}
+                }
+            }
+
+            // food keeps counting down below zero, so it doubles as the starvation clock
+            if state == HungerState::Starving && hunger.food % STARVATION_INTERVAL == 0 {
+                health.current -= 1;
+                log.add(LogKind::Hurt, "Hunger gnaws at you.");
+            }
+        });
+}
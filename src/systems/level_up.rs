@@ -0,0 +1,38 @@
+use crate::prelude::*;
+
+const HEALTH_PER_LEVEL: i32 = 2;
+// base damage increases on every nth level
+const DAMAGE_LEVEL_INTERVAL: i32 = 2;
+
+#[system]
+#[write_component(Experience)]
+#[write_component(Health)]
+#[write_component(Damage)]
+pub fn level_up(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] log: &mut GameLog
+) {
+    let mut levellers = <(Entity, &mut Experience, &mut Health, &mut Damage)>::query();
+
+    levellers
+        .iter_mut(ecs)
+        .filter(|(_, xp, _, _)| xp.current >= xp.next_level)
+        .for_each(|(entity, xp, health, damage)| {
+            // a big kill can be worth more than one level
+            while xp.current >= xp.next_level {
+                xp.current -= xp.next_level;
+                xp.level += 1;
+                xp.next_level = Experience::needed_for(xp.level);
+
+                health.max += HEALTH_PER_LEVEL;
+                health.current = i32::min(health.max, health.current + HEALTH_PER_LEVEL);
+                if xp.level % DAMAGE_LEVEL_INTERVAL == 0 {
+                    damage.0 += 1;
+                }
+            }
+
+            commands.add_component(*entity, LevelUp { level: xp.level });
+            log.add(LogKind::Level, format!("Welcome to character level {}!", xp.level));
+        });
+}
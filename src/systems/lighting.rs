@@ -0,0 +1,77 @@
+use crate::prelude::*;
+
+// the first level is dimly lit all over, and it gets darker further down
+const SURFACE_LIGHT: f32 = 0.6;
+const LIGHT_LOST_PER_LEVEL: f32 = 0.25;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(LightSource)]
+#[read_component(Equipped)]
+#[read_component(Enchantment)]
+#[write_component(FieldOfView)]
+pub fn lighting(ecs: &mut SubWorld, #[resource] map: &mut Map) {
+    // what everyone can see depends on the light, so it is looked at again
+    if light_map(ecs, map) {
+        <&mut FieldOfView>::query()
+            .iter_mut(ecs)
+            .for_each(|fov| fov.is_dirty = true);
+    }
+}
+
+// works out how brightly each tile is lit, returning whether anything changed.
+// also run once when a level is built, before anyone has taken a turn
+pub fn light_map<W: EntityStore>(ecs: &W, map: &mut Map) -> bool {
+    let depth = <&Player>::query()
+        .iter(ecs)
+        .map(|player| player.map_level)
+        .next()
+        .unwrap_or(0);
+    let ambient = f32::max(0.0, SURFACE_LIGHT - LIGHT_LOST_PER_LEVEL * depth as f32);
+
+    let mut light = vec![ambient; map.tiles.len()];
+    let mut light_colour = vec![RGB::named(WHITE); map.tiles.len()];
+
+    let mut sources: Vec<(Point, LightSource)> = <(&Point, &LightSource)>::query()
+        .iter(ecs)
+        .map(|(pos, source)| (*pos, *source))
+        .collect();
+
+    // equipped lights shine from whoever is holding them, enchanted ones further
+    <(&Equipped, &LightSource, Option<&Enchantment>)>::query()
+        .iter(ecs)
+        .for_each(|(equipped, source, enchantment)| {
+            if let Ok(owner) = ecs.entry_ref(equipped.owner)
+                && let Ok(pos) = owner.get_component::<Point>()
+            {
+                let radius = source.radius + enchantment.map_or(0, |e| e.0);
+                sources.push((*pos, LightSource { radius, ..*source }));
+            }
+        });
+
+    // light fades with distance, and each tile takes the brightest light reaching it
+    sources
+        .iter()
+        .filter(|(_, source)| source.radius > 0)
+        .for_each(|(pos, source)| {
+            field_of_view_set(*pos, source.radius, map)
+                .iter()
+                .filter_map(|pt| map.try_idx(*pt).map(|idx| (idx, *pt)))
+                .for_each(|(idx, pt)| {
+                    let distance = DistanceAlg::Pythagoras.distance2d(*pos, pt);
+                    let strength = 1.0 - distance / (source.radius + 1) as f32;
+                    if strength > light[idx] {
+                        light[idx] = strength;
+                        light_colour[idx] = source.colour;
+                    }
+                });
+        });
+
+    if light == map.light && light_colour == map.light_colour {
+        return false;
+    }
+    map.light = light;
+    map.light_colour = light_colour;
+    true
+}
@@ -0,0 +1,29 @@
+use crate::prelude::*;
+
+const LOG_LINES: usize = 6;
+
+#[system]
+pub fn log_render(#[resource] log: &GameLog) {
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+
+    // newest entry on the bottom line, older entries scroll upwards
+    let top = SCREEN_HEIGHT * 2 - LOG_LINES as i32 - 1;
+    log.recent(LOG_LINES)
+        .iter()
+        .enumerate()
+        .for_each(|(i, entry)| {
+            draw_batch.print_color(
+                Point::new(1, top + i as i32),
+                &entry.text,
+                ColorPair::new(entry.kind.colour(), BLACK)
+            );
+        });
+    draw_batch.print_color(
+        Point::new(1, SCREEN_HEIGHT * 2 - 1),
+        "Space : wait   R : rest   I : inventory   L : message history",
+        ColorPair::new(GREY, BLACK)
+    );
+
+    draw_batch.submit(10050).expect("Batch error");
+}
@@ -1,6 +1,12 @@
 use crate::prelude::*;
 
+// the darkest a visible tile is drawn, however little light reaches it
+const MIN_BRIGHTNESS: f32 = 0.4;
+// remembered tiles are drawn without colour at this brightness
+const REMEMBERED_BRIGHTNESS: f32 = 0.3;
+
 #[system]
+#[read_component(Point)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
 pub fn map_render(
@@ -9,11 +15,11 @@ pub fn map_render(
     #[resource] camera: &mut Camera,
     #[resource] theme: &Box<dyn MapTheme>
 ) {
-    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    let mut fov = <(&Point, &FieldOfView)>::query().filter(component::<Player>());
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(0);
 
-    let player_fov = fov.iter(ecs).next().unwrap();
+    let (player_pos, player_fov) = fov.iter(ecs).next().unwrap();
 
     for y in camera.top_y..=camera.bottom_y {
         for x in camera.left_x..=camera.right_x {
@@ -24,16 +30,27 @@ pub fn map_render(
             if map.in_bounds(pt) &&
                 (player_fov.visible_tiles.contains(&pt) | map.revealed_tiles[idx])
             {
-                let tint = if player_fov.visible_tiles.contains(&pt) {
-                    WHITE
+                let (fg, bg) = theme.tile_colours(map.tiles[idx]);
+                let (fg, bg) = if player_fov.visible_tiles.contains(&pt) {
+                    // visible tiles take on the colour of the light falling on them,
+                    // fading towards the edge of what the player can see
+                    let distance = DistanceAlg::Pythagoras.distance2d(*player_pos, pt);
+                    let falloff = 1.0 - distance / (player_fov.radius as f32 * 2.0);
+                    let brightness = MIN_BRIGHTNESS +
+                        (1.0 - MIN_BRIGHTNESS) * f32::clamp(map.light[idx] * falloff, 0.0, 1.0);
+                    let light = map.light_colour[idx];
+                    (fg * light * brightness, bg * light * brightness)
                 } else {
-                    DARKGRAY
+                    (
+                        fg.desaturate() * REMEMBERED_BRIGHTNESS,
+                        bg.desaturate() * REMEMBERED_BRIGHTNESS
+                    )
                 };
 
                 let glyph = theme.tile_to_render(map.tiles[idx]);
-                draw_batch.set(pt - offset, ColorPair::new(tint, BLACK), glyph);
+                draw_batch.set(pt - offset, ColorPair::new(fg, bg), glyph);
             }
         }
     }
     draw_batch.submit(0).expect("Batch error");
-}
\ No newline at end of file
+}
@@ -1,18 +1,37 @@
+mod boss;
+mod buy;
 mod chasing;
 mod combat;
+mod confusion;
+mod drop_item;
 mod end_turn;
+mod equip;
+mod fleeing;
 mod entity_render;
 mod fov;
+mod gold;
 mod hud;
+mod hunger;
+mod level_up;
+mod lighting;
+mod log_render;
 mod map_render;
 mod movement;
+mod noise;
+mod patrol;
 mod player_input;
 mod random_move;
+mod regeneration;
+mod spellcasting;
+mod stationary;
+mod targeting;
 mod tooltips;
 mod use_item;
 
 use crate::prelude::*;
 
+pub use lighting::light_map;
+
 pub fn build_input_scheduler() -> Schedule {
     Schedule::builder()
         .add_system(player_input::player_input_system())
@@ -21,22 +40,44 @@ pub fn build_input_scheduler() -> Schedule {
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
+        .add_system(log_render::log_render_system())
         .add_system(tooltips::tooltips_system())
         .build()
 }
 
+pub fn build_targeting_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(map_render::map_render_system())
+        .add_system(entity_render::entity_render_system())
+        .add_system(hud::hud_system())
+        .add_system(log_render::log_render_system())
+        .add_system(targeting::targeting_system())
+        .build()
+}
+
 pub fn build_player_scheduler() -> Schedule {
     Schedule::builder()
+        .add_system(equip::equip_system())
+        .add_system(drop_item::drop_items_system())
+        .add_system(buy::buy_items_system())
         .add_system(use_item::use_items_system())
+        .flush()
         .add_system(combat::combat_system())
         .flush()
+        .add_system(level_up::level_up_system())
+        .add_system(hunger::hunger_system())
+        .add_system(boss::boss_phases_system())
         .add_system(movement::movement_system())
         .flush()
+        .add_system(lighting::lighting_system())
         .add_system(fov::fov_system())
+        .add_system(noise::noise_system())
+        .add_system(gold::collect_gold_system())
         .flush()
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
+        .add_system(log_render::log_render_system())
         .add_system(tooltips::tooltips_system())
         .add_system(end_turn::end_turn_system())
         .build()
@@ -46,17 +87,28 @@ pub fn build_monster_scheduler() -> Schedule {
     Schedule::builder()
         .add_system(random_move::random_move_system())
         .add_system(chasing::chasing_system())
+        .add_system(stationary::stationary_system())
+        .add_system(fleeing::fleeing_system())
+        .add_system(patrol::patrol_system())
+        .add_system(spellcasting::spellcasting_system())
+        .add_system(confusion::confusion_system())
         .flush()
         .add_system(use_item::use_items_system())
+        .flush()
         .add_system(combat::combat_system())
         .flush()
+        .add_system(boss::boss_phases_system())
+        .add_system(regeneration::regeneration_system())
         .add_system(movement::movement_system())
         .flush()
+        .add_system(lighting::lighting_system())
         .add_system(fov::fov_system())
+        .add_system(noise::noise_system())
         .flush()
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
+        .add_system(log_render::log_render_system())
         .add_system(tooltips::tooltips_system())
         .add_system(end_turn::end_turn_system())
         .build()
@@ -1,31 +1,50 @@
 use crate::prelude::*;
 
+const FOOTSTEP_VOLUME: i32 = 1;
+
 // a system to receive want to move messages
+#[allow(clippy::too_many_arguments)]
 #[system(for_each)]
 #[read_component(Player)]
 #[read_component(FieldOfView)]
+#[read_component(Item)]
+#[read_component(Name)]
+#[read_component(Point)]
 pub fn movement(
     entity: &Entity,
     want_move: &WantsToMove,
     #[resource] map: &mut Map,
     #[resource] camera: &mut Camera,
+    #[resource] log: &mut GameLog,
+    #[resource] identification: &Identification,
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer
 ) {
-    if map.can_enter_tile(want_move.destination) {
+    // the mover may have been killed earlier in the turn
+    if map.can_enter_tile(want_move.destination)
+        && let Ok(entry) = ecs.entry_ref(want_move.entity)
+    {
         commands.add_component(want_move.entity, want_move.destination);
 
-        if let Ok(entry) = ecs.entry_ref(want_move.entity) {
-            if let Ok(fov) = entry.get_component::<FieldOfView>() {
-                // update field of view if found on entry
-                commands.add_component(want_move.entity, fov.clone_dirty());
+        if let Ok(fov) = entry.get_component::<FieldOfView>() {
+            // update field of view if found on entry
+            commands.add_component(want_move.entity, fov.clone_dirty());
 
-                if entry.get_component::<Player>().is_ok() {
-                    camera.on_player_move(want_move.destination);
-                    fov.visible_tiles.iter().for_each(|pos| {
-                        map.revealed_tiles[map_idx(pos.x, pos.y)] = true;
-                    })
-                }
+            if entry.get_component::<Player>().is_ok() {
+                camera.on_player_move(want_move.destination);
+                commands.push(((), Noise {
+                    pos: want_move.destination,
+                    volume: FOOTSTEP_VOLUME
+                }));
+                <(&Item, &Point, &Name)>::query()
+                    .iter(ecs)
+                    .filter(|(_, pos, _)| **pos == want_move.destination)
+                    .for_each(|(_, _, name)| {
+                        log.add(LogKind::Info, format!("You see a {} here.", identification.name_of(&name.0)));
+                    });
+                fov.visible_tiles.iter().for_each(|pos| {
+                    map.revealed_tiles[map_idx(pos.x, pos.y)] = true;
+                })
             }
         }
     }
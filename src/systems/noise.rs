@@ -0,0 +1,60 @@
+use crate::prelude::*;
+
+use std::collections::HashSet;
+
+#[system]
+#[read_component(Noise)]
+#[read_component(Point)]
+#[read_component(Asleep)]
+#[read_component(Perception)]
+#[read_component(Name)]
+#[read_component(FieldOfView)]
+#[read_component(Player)]
+pub fn noise(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] log: &mut GameLog
+) {
+    let mut noises = <(Entity, &Noise)>::query();
+    let mut sleepers = <(Entity, &Point, &Perception, &Name)>::query().filter(component::<Asleep>());
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+
+    let player_fov = fov.iter(ecs).next().unwrap();
+    let max_perception = sleepers
+        .iter(ecs)
+        .map(|(_, _, perception, _)| perception.0)
+        .max()
+        .unwrap_or(0);
+
+    let mut woken = HashSet::new();
+    noises.iter(ecs).for_each(|(message, noise)| {
+        // noise travels along walkable tiles, so walls muffle it
+        let reach = DijkstraMap::new(
+            SCREEN_WIDTH,
+            SCREEN_HEIGHT,
+            &[map.point2d_to_index(noise.pos)],
+            map,
+            (noise.volume + max_perception + 1) as f32
+        );
+
+        sleepers
+            .iter(ecs)
+            .filter(|(_, pos, perception, _)| {
+                let distance = reach.map[map.point2d_to_index(**pos)];
+                **pos == noise.pos || distance <= (noise.volume + perception.0) as f32
+            })
+            .for_each(|(entity, pos, _, name)| {
+                if !woken.insert(*entity) {
+                    // already woken by an earlier noise this turn
+                    return;
+                }
+                commands.remove_component::<Asleep>(*entity);
+                if player_fov.visible_tiles.contains(pos) {
+                    log.add(LogKind::Info, format!("The {} wakes up!", name.0));
+                }
+            });
+
+        commands.remove(*message);
+    });
+}
@@ -0,0 +1,59 @@
+use crate::prelude::*;
+use super::fleeing::is_fleeing;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Patrolling)]
+#[read_component(Health)]
+#[read_component(Player)]
+#[read_component(FleesWhenWounded)]
+#[read_component(FieldOfView)]
+#[read_component(Asleep)]
+#[read_component(Confused)]
+#[read_component(Faction)]
+pub fn patrol(
+    #[resource] map: &Map,
+    #[resource] factions: &Factions,
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer
+) {
+    let mut movers = <(Entity, &Point, &Patrolling)>::query().filter(!component::<Asleep>() & !component::<Confused>());
+    let mut positions = <(Entity, &Point, &Health)>::query();
+    let player = <Entity>::query().filter(component::<Player>()).iter(ecs).next().unwrap();
+
+    movers.iter(ecs).for_each(|(entity, pos, patrol)| {
+        if is_fleeing(ecs, factions, *entity, *player) {
+            return;
+        }
+
+        let destination = *pos + patrol.direction;
+
+        let mut blocked = !map.can_enter_tile(destination);
+        let mut attacked = false;
+        positions
+            .iter(ecs)
+            .filter(|(_, target_pos, _)| **target_pos == destination)
+            .for_each(|(target, _, _)| {
+                if factions.entity_is_hostile(ecs, *entity, *target) {
+                    commands.push(((), WantsToAttack {
+                        attacker: *entity,
+                        target: *target
+                    }));
+                    attacked = true;
+                }
+                blocked = true;
+            });
+
+        if !blocked {
+            commands.push(((), WantsToMove {
+                entity: *entity,
+                destination
+            }));
+        } else if !attacked {
+            // turn clockwise and try again next turn, so patrols follow the walls
+            commands.add_component(*entity, Patrolling {
+                direction: Point::new(-patrol.direction.y, patrol.direction.x)
+            });
+        }
+    });
+}
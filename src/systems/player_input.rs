@@ -1,5 +1,8 @@
 use crate::prelude::*;
 
+use std::collections::HashMap;
+
+#[allow(clippy::too_many_arguments)]
 #[system]
 #[read_component(Point)]
 #[read_component(Player)]
@@ -7,16 +10,51 @@ use crate::prelude::*;
 #[write_component(Health)]
 #[read_component(Item)]
 #[read_component(Carried)]
-#[read_component(Weapon)]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(LevelUp)]
+#[read_component(Name)]
+#[read_component(Faction)]
+#[read_component(Stackable)]
+#[read_component(Quantity)]
+#[read_component(Ranged)]
+#[read_component(Provides)]
+#[read_component(FieldOfView)]
+#[read_component(Charges)]
+#[read_component(Resting)]
+#[read_component(Hunger)]
+#[read_component(Shopkeeper)]
+#[read_component(ForSale)]
+#[read_component(Gold)]
+#[read_component(Enchantment)]
+#[read_component(Appraised)]
+#[read_component(Cursed)]
 pub fn player_input(
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
     #[resource] key: &Option<VirtualKeyCode>,
-    #[resource] turn_state: &mut TurnState
+    #[resource] turn_state: &mut TurnState,
+    #[resource] log: &mut GameLog,
+    #[resource] factions: &Factions,
+    #[resource] identification: &Identification,
+    #[resource] targeting: &mut Targeting,
+    #[resource] shop: &mut ShopMenu
 ) {        
     let mut players = <(Entity, &Point)>::query().filter(component::<Player>());
 
     if let Some(key) = *key {            
+        // any key press dismisses the level up prompt
+        <Entity>::query()
+            .filter(component::<Player>() & component::<LevelUp>())
+            .iter(ecs)
+            .for_each(|entity| commands.remove_component::<LevelUp>(*entity));
+
+        // and wakes the player from a rest
+        <Entity>::query()
+            .filter(component::<Player>() & component::<Resting>())
+            .iter(ecs)
+            .for_each(|entity| commands.remove_component::<Resting>(*entity));
+
         let delta = match key {
             VirtualKeyCode::Left => Point::new(-1, 0),
             VirtualKeyCode::Right => Point::new(1, 0),
@@ -28,40 +66,128 @@ pub fn player_input(
                     .find_map(|(entity, pos)| Some((*entity, *pos)))
                     .unwrap();
 
-                let mut items = <(Entity, &Item, &Point)>::query();
+                let mut carried = carried_items(ecs, player, identification).len();
+                // stacks already in the backpack, by name, with their current size
+                let mut stacks: HashMap<String, (Entity, i32)> = <(Entity, &Carried, &Name, &Quantity)>::query()
+                    .filter(component::<Stackable>())
+                    .iter(ecs)
+                    .filter(|(_, carried, _, _)| carried.0 == player)
+                    .map(|(entity, _, name, quantity)| (name.0.clone(), (*entity, quantity.0)))
+                    .collect();
+
+                // gold is collected by walking over it, never carried
+                let mut items = <(Entity, &Item, &Point)>::query().filter(!component::<Gold>());
                 items
                     .iter(ecs)
                     .filter(|(_entity, _item, item_pos)| **item_pos == player_pos)
                     .for_each(|(entity, _item, _item_pos)| {
+                        let entry = ecs.entry_ref(*entity).unwrap();
+                        let name = entry.get_component::<Name>().map(|n| n.0.clone()).unwrap_or_default();
+                        let quantity = entry.get_component::<Quantity>().map(|q| q.0).unwrap_or(1);
+
+                        // wares have to be paid for, unless nobody is left to pay
+                        if let Ok(sale) = entry.get_component::<ForSale>() {
+                            if ecs.entry_ref(sale.shopkeeper).is_ok() {
+                                log.add(
+                                    LogKind::Item,
+                                    format!("You must pay for the {} first.", identification.name_of(&name))
+                                );
+                                return;
+                            }
+                            commands.remove_component::<ForSale>(*entity);
+                        }
+
+                        if entry.get_component::<Stackable>().is_ok()
+                            && let Some((stack, count)) = stacks.get_mut(&name)
+                        {
+                            // merge into the existing stack instead of taking a new slot
+                            *count += quantity;
+                            commands.add_component(*stack, Quantity(*count));
+                            commands.remove(*entity);
+                            log.add(
+                                LogKind::Item,
+                                format!("You pick up the {} (x{}).", identification.name_of(&name), count)
+                            );
+                            return;
+                        }
+
+                        if carried >= INVENTORY_CAPACITY {
+                            log.add(LogKind::Item, "Your backpack is full.");
+                            return;
+                        }
+                        carried += 1;
                         commands.remove_component::<Point>(*entity);
                         commands.add_component(*entity, Carried(player));
+                        log.add(LogKind::Item, format!("You pick up the {}.", identification.name_of(&name)));
+                        if entry.get_component::<Stackable>().is_ok() {
+                            stacks.insert(name, (*entity, quantity));
+                        }
 
-                        if let Ok(e) = ecs.entry_ref(*entity) {
-                            if e.get_component::<Weapon>().is_ok() {
-                                <(Entity, &Carried, &Weapon)>::query()
-                                    .iter(ecs)
-                                    .filter(|(_, c, _)| c.0 == player)
-                                    .for_each(|(e, _, _)| {
-                                        commands.remove(*e);
-                                    });
+                        // equipment goes straight into a free slot
+                        if let Ok(equippable) = ecs.entry_ref(*entity).unwrap().get_component::<Equippable>() {
+                            let slot_taken = <&Equipped>::query()
+                                .iter(ecs)
+                                .any(|equipped| equipped.owner == player && equipped.slot == equippable.0);
+                            if !slot_taken {
+                                commands.push(((), WantsToEquip {
+                                    entity: player,
+                                    item: *entity
+                                }));
                             }
                         }
                     });
 
                 Point::new(0, 0)
             },
-            VirtualKeyCode::Key1 => use_item(0, ecs, commands),
-            VirtualKeyCode::Key2 => use_item(1, ecs, commands),
-            VirtualKeyCode::Key3 => use_item(2, ecs, commands),
-            VirtualKeyCode::Key4 => use_item(3, ecs, commands),
-            VirtualKeyCode::Key5 => use_item(4, ecs, commands),
-            VirtualKeyCode::Key6 => use_item(5, ecs, commands),
-            VirtualKeyCode::Key7 => use_item(6, ecs, commands),
-            VirtualKeyCode::Key8 => use_item(7, ecs, commands),
-            VirtualKeyCode::Key9 => use_item(8, ecs, commands),
-            _ => Point::new(0, 0)
+            VirtualKeyCode::I => {
+                *turn_state = TurnState::ShowingInventory;
+                return;
+            },
+            VirtualKeyCode::L => {
+                // looking at the log does not use up a turn
+                *turn_state = TurnState::ShowingLog;
+                return;
+            },
+            VirtualKeyCode::Space => Point::zero(),
+            VirtualKeyCode::R => {
+                let player = players
+                    .iter(ecs)
+                    .map(|(entity, _)| *entity)
+                    .next()
+                    .unwrap();
+
+                match rest_interruption(ecs, factions, player) {
+                    Some(reason) => {
+                        log.add(LogKind::Info, reason);
+                        return;
+                    }
+                    None => {
+                        log.add(LogKind::Info, "You settle down to rest.");
+                        commands.add_component(player, Resting);
+                    }
+                }
+                Point::zero()
+            },
+            VirtualKeyCode::Key1 | VirtualKeyCode::Key2 | VirtualKeyCode::Key3 |
+            VirtualKeyCode::Key4 | VirtualKeyCode::Key5 | VirtualKeyCode::Key6 |
+            VirtualKeyCode::Key7 | VirtualKeyCode::Key8 | VirtualKeyCode::Key9 => {
+                let n = key as usize - VirtualKeyCode::Key1 as usize;
+                match use_item(n, ecs, commands, log, identification, targeting) {
+                    Some(delta) => delta,
+                    // nothing was used, so no turn is taken
+                    None => return
+                }
+            },
+            // keys that do nothing should not cost a turn
+            _ => return
         };
 
+        // aimed items wait for a target before the turn is taken
+        if targeting.item.is_some() {
+            *turn_state = TurnState::Targeting;
+            return;
+        }
+
         let (player_entity, destination) = players
             .iter(ecs)
             .find_map(|(entity, pos)| Some((*entity, *pos + delta)))
@@ -70,21 +196,43 @@ pub fn player_input(
         let mut enemies = <(Entity, &Point)>::query().filter(component::<Enemy>());
     
         if delta.x != 0 || delta.y != 0 {
+            // bumping into a shopkeeper opens the shop, browsing is free
+            let shopkeeper = <(Entity, &Point)>::query()
+                .filter(component::<Shopkeeper>())
+                .iter(ecs)
+                .find(|(_, pos)| **pos == destination)
+                .map(|(entity, _)| *entity);
+            if let Some(shopkeeper) = shopkeeper {
+                *shop = ShopMenu::open(shopkeeper);
+                *turn_state = TurnState::Shopping;
+                return;
+            }
+
             let mut hit_something = false;
+            let mut blocked = false;
             enemies
                 .iter(ecs)
                 .filter(|(_, pos)| {
                     **pos == destination
                 })
                 .for_each(|(entity, _)| {
-                    // found a target, attack
-                    hit_something = true;
-                    commands.push(((), WantsToAttack{
-                        attacker: player_entity,
-                        target: *entity
-                    }));
+                    // found a target, attack unless it is on our side
+                    if factions.entity_is_hostile(ecs, player_entity, *entity) {
+                        hit_something = true;
+                        commands.push(((), WantsToAttack{
+                            attacker: player_entity,
+                            target: *entity
+                        }));
+                    } else {
+                        blocked = true;
+                    }
                 });
 
+            // bumping into an ally does nothing, so it does not cost a turn
+            if blocked && !hit_something {
+                return;
+            }
+
             if !hit_something {
                 commands.push(((), WantsToMove{
                     entity: player_entity,
@@ -94,33 +242,120 @@ pub fn player_input(
         }
        
         *turn_state = TurnState::PlayerTurn;
+    } else if let Some(player) = <Entity>::query()
+        .filter(component::<Player>() & component::<Resting>())
+        .iter(ecs)
+        .next()
+    {
+        // resting passes turns on its own until something ends it
+        match rest_interruption(ecs, factions, *player) {
+            Some(reason) => {
+                log.add(LogKind::Info, reason);
+                commands.remove_component::<Resting>(*player);
+            }
+            None => *turn_state = TurnState::PlayerTurn
+        }
+    }
+}
+
+// why the player cannot rest right now, if anything stops them
+fn rest_interruption(ecs: &SubWorld, factions: &Factions, player: Entity) -> Option<String> {
+    let entry = ecs.entry_ref(player).unwrap();
+    let resting = entry.get_component::<Resting>().is_ok();
+
+    if let Ok(fov) = entry.get_component::<FieldOfView>() {
+        let enemy = <(Entity, &Point, &Name)>::query()
+            .filter(component::<Enemy>())
+            .iter(ecs)
+            .find(|(entity, pos, _)| {
+                fov.visible_tiles.contains(pos) && factions.entity_is_hostile(ecs, player, **entity)
+            });
+        if let Some((_, _, name)) = enemy {
+            return Some(if resting {
+                format!("The {} interrupts your rest.", name.0)
+            } else {
+                format!("You cannot rest with the {} nearby.", name.0)
+            });
+        }
     }
+
+    if let Ok(hunger) = entry.get_component::<Hunger>()
+        && hunger.state().regen_multiplier().is_none()
+    {
+        return Some("You are too hungry to rest.".to_string());
+    }
+
+    if let Ok(health) = entry.get_component::<Health>()
+        && health.current >= health.max
+    {
+        return Some(if resting {
+            "You feel rested.".to_string()
+        } else {
+            "You are already at full health.".to_string()
+        });
+    }
+
+    None
 }
 
-fn use_item(n: usize, ecs: &mut SubWorld, commands: &mut CommandBuffer) -> Point {
+fn use_item(
+    n: usize,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    log: &mut GameLog,
+    identification: &Identification,
+    targeting: &mut Targeting
+) -> Option<Point> {
     let player_entity = <(Entity, &Player)>::query()
         .iter(ecs)
         .find_map(|(entity, _player)| Some(*entity))
         .unwrap();
 
-    let item_entity = <(Entity, &Item, &Carried)>::query()
-        .iter(ecs)
-        .filter(|(_, _, carried)| carried.0 == player_entity)
-        .enumerate()
-        .filter(|(item_count, (_, _, _))| *item_count == n)
-        .find_map(|(_, (item_entity, _, _))| Some(*item_entity));
+    let item_entity = carried_items(ecs, player_entity, identification)
+        .get(n)
+        .map(|(item_entity, _)| *item_entity);
 
     if let Some(item_entity) = item_entity {
-        commands.push(
-            (
-                (),
-                ActivateItem {
-                    used_by: player_entity,
-                    item: item_entity
-                }
-            )
-        );
-    }
+        let equippable = ecs
+            .entry_ref(item_entity)
+            .unwrap()
+            .get_component::<Equippable>()
+            .is_ok();
 
-    Point::zero()
+        let ranged = ecs
+            .entry_ref(item_entity)
+            .unwrap()
+            .get_component::<Ranged>()
+            .is_ok();
+
+        // equipment is worn or taken off rather than used up
+        if ranged && out_of_charges(ecs, item_entity) {
+            log.add(
+                LogKind::Item,
+                format!("The {} has no charges left.", item_name(ecs, item_entity, identification))
+            );
+            return None;
+        } else if ranged {
+            targeting.begin(ecs, player_entity, item_entity);
+        } else if equippable {
+            commands.push(((), WantsToEquip {
+                entity: player_entity,
+                item: item_entity
+            }));
+        } else {
+            commands.push(
+                (
+                    (),
+                    ActivateItem {
+                        used_by: player_entity,
+                        item: item_entity,
+                        target: None
+                    }
+                )
+            );
+        }
+        Some(Point::zero())
+    } else {
+        None
+    }
 }
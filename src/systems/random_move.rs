@@ -1,29 +1,63 @@
 use crate::prelude::*;
+use super::fleeing::is_fleeing;
 
 #[system]
 #[read_component(Point)]
 #[read_component(MovingRandomly)]
 #[read_component(Health)]
 #[read_component(Player)]
-pub fn random_move(ecs: &SubWorld, commands: &mut CommandBuffer) {
-    let mut movers = <(Entity, &Point, &MovingRandomly)>::query();
+#[read_component(FleesWhenWounded)]
+#[read_component(FieldOfView)]
+#[read_component(ChasingPlayer)]
+#[read_component(Asleep)]
+#[read_component(Confused)]
+#[read_component(Faction)]
+#[read_component(Item)]
+pub fn random_move(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] factions: &Factions,
+    #[resource] map: &Map,
+    #[resource] flow_fields: &mut FlowFields
+) {
+    let mut movers = <(Entity, &Point, &MovingRandomly)>::query().filter(!component::<Asleep>() & !component::<Confused>());
     let mut positions = <(Entity, &Point, &Health)>::query();
+    let (player, player_pos) = <(Entity, &Point)>::query().filter(component::<Player>()).iter(ecs).next().unwrap();
+    flow_fields.update_items(ecs);
     movers.iter(ecs).for_each(|(entity, pos, _)| {
+        if is_fleeing(ecs, factions, *entity, *player) || is_hunting(ecs, *entity, player_pos) {
+            return;
+        }
+
+        // wanderers are drawn to any item they can see lying around
+        let sees_item = ecs.entry_ref(*entity)
+            .ok()
+            .and_then(|entry| entry.get_component::<FieldOfView>().ok().cloned())
+            .is_some_and(|fov| flow_fields.items().iter().any(|item| item != pos && fov.visible_tiles.contains(item)));
+        let toward_item = if sees_item {
+            flow_fields.ensure(FlowField::TowardItems, map, ecs);
+            let idx = map_idx(pos.x, pos.y);
+            DijkstraMap::find_lowest_exit(flow_fields.get(FlowField::TowardItems), idx, map)
+                .map(|exit| map.index_to_point2d(exit))
+        } else {
+            None
+        };
+
         let mut rng = RandomNumberGenerator::new();
-        let destination = match rng.range(0, 4) {
+        let destination = toward_item.unwrap_or_else(|| match rng.range(0, 4) {
             0 => Point::new(-1, 0),
             1 => Point::new(1, 0),
             2 => Point::new(0, -1),
             _ => Point::new(0, 1),
-        } + *pos;
+        } + *pos);
 
         let mut attacked = false;
         positions
             .iter(ecs)
             .filter(|(_, target_pos, _)| **target_pos == destination)
             .for_each(|(target, _, _)| {
-                if ecs.entry_ref(*target).unwrap().get_component::<Player>().is_ok() {
-                    // found a player in destination, attack
+                if factions.entity_is_hostile(ecs, *entity, *target) {
+                    // found an enemy in destination, attack
                     commands.push(((), WantsToAttack {
                         attacker: *entity,
                         target: *target
@@ -38,3 +72,15 @@ pub fn random_move(ecs: &SubWorld, commands: &mut CommandBuffer) {
         }
     });
 }
+
+// a chaser that gave up its search goes back to hunting as soon as it spots the player
+fn is_hunting(ecs: &SubWorld, entity: Entity, player_pos: &Point) -> bool {
+    if let Ok(entry) = ecs.entry_ref(entity)
+        && entry.get_component::<ChasingPlayer>().is_ok()
+        && let Ok(fov) = entry.get_component::<FieldOfView>()
+    {
+        fov.visible_tiles.contains(player_pos)
+    } else {
+        false
+    }
+}
@@ -0,0 +1,30 @@
+use crate::prelude::*;
+
+// ticked once a full turn, after everyone has had their go
+#[system]
+#[write_component(Regeneration)]
+#[write_component(Health)]
+#[read_component(Hunger)]
+pub fn regeneration(ecs: &mut SubWorld) {
+    <(&mut Regeneration, &mut Health, Option<&Hunger>)>::query()
+        .iter_mut(ecs)
+        .filter(|(_, health, _)| health.current > 0)
+        .for_each(|(regen, health, hunger)| {
+            // only the player gets hungry, and a hungry body heals slowly if at all
+            let multiplier = match hunger {
+                Some(hunger) => hunger.state().regen_multiplier(),
+                None => Some(1)
+            };
+
+            match multiplier {
+                Some(multiplier) if health.current < health.max => {
+                    regen.timer += 1;
+                    if regen.timer >= regen.interval * multiplier {
+                        regen.timer = 0;
+                        health.current += 1;
+                    }
+                }
+                _ => regen.timer = 0
+            }
+        });
+}
@@ -0,0 +1,225 @@
+use crate::prelude::*;
+use super::fleeing::is_fleeing;
+
+use std::collections::HashSet;
+
+const NEIGHBOURS: [Point; 4] = [
+    Point { x: -1, y: 0 },
+    Point { x: 1, y: 0 },
+    Point { x: 0, y: -1 },
+    Point { x: 0, y: 1 }
+];
+// the most summoned minions a caster can have alive at once
+const MAX_SUMMONS: usize = 3;
+
+// casters pick between casting a ready spell and moving each turn, keeping
+// their distance from enemies when they can
+#[system]
+#[read_component(Point)]
+#[read_component(Spellcaster)]
+#[read_component(FieldOfView)]
+#[write_component(Health)]
+#[read_component(Faction)]
+#[read_component(Player)]
+#[read_component(Asleep)]
+#[read_component(Confused)]
+#[read_component(FleesWhenWounded)]
+#[read_component(Name)]
+#[read_component(Summoned)]
+pub fn spellcasting(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] factions: &Factions,
+    #[resource] templates: &Templates,
+    #[resource] log: &mut GameLog
+) {
+    let mut rng = RandomNumberGenerator::new();
+    let depth = <&Player>::query()
+        .iter(ecs)
+        .next()
+        .map(|player| player.map_level as usize)
+        .unwrap_or(0);
+    let (player, player_fov) = <(Entity, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(entity, fov)| (*entity, fov.visible_tiles.clone()))
+        .next()
+        .unwrap();
+
+    let creatures: Vec<(Entity, Point, Faction, Health)> = <(Entity, &Point, &Faction, &Health)>::query()
+        .iter(ecs)
+        .map(|(entity, pos, faction, health)| (*entity, *pos, faction.clone(), *health))
+        .collect();
+    let mut occupied: HashSet<Point> = creatures.iter().map(|c| c.1).collect();
+
+    let casters: Vec<(Entity, Point, Spellcaster, FieldOfView, Faction, String)> =
+        <(Entity, &Point, &Spellcaster, &FieldOfView, &Faction, &Name)>::query()
+            .filter(!component::<Asleep>() & !component::<Confused>())
+            .iter(ecs)
+            .filter(|(entity, ..)| !is_fleeing(ecs, factions, **entity, player))
+            .map(|(entity, pos, caster, fov, faction, name)| {
+                (*entity, *pos, caster.clone(), fov.clone(), faction.clone(), name.0.clone())
+            })
+            .collect();
+
+    let name_of = |entity: Entity| {
+        ecs.entry_ref(entity)
+            .ok()
+            .and_then(|e| e.get_component::<Name>().ok().map(|n| format!("the {}", n.0)))
+            .unwrap_or_else(|| "you".to_string())
+    };
+
+    let mut heals = Vec::<(Entity, i32)>::new();
+    casters.into_iter().for_each(|(entity, pos, mut caster, fov, faction, name)| {
+        caster.spells.iter_mut().for_each(|spell| spell.ready_in = i32::max(0, spell.ready_in - 1));
+        let seen = player_fov.contains(&pos);
+
+        let enemy = creatures
+            .iter()
+            .filter(|(target, target_pos, target_faction, _)| {
+                *target != entity
+                    && fov.visible_tiles.contains(target_pos)
+                    && factions.is_hostile(&faction, target_faction)
+            })
+            .map(|(target, target_pos, _, _)| (*target, *target_pos, DistanceAlg::Pythagoras.distance2d(pos, *target_pos)))
+            .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+
+        let mut acted = false;
+        for spell in caster.spells.iter_mut().filter(|spell| spell.ready_in == 0) {
+            acted = match &spell.kind {
+                SpellKind::Bolt => {
+                    if let Some((target, target_pos, distance)) = enemy
+                        && distance <= spell.range as f32
+                        && clear_shot(map, pos, target_pos)
+                    {
+                        if seen {
+                            log.add(LogKind::Attack, format!("The {} casts a bolt at {}.", name, name_of(target)));
+                        }
+                        commands.push(((), WantsToAttack {
+                            attacker: entity,
+                            target
+                        }));
+                        true
+                    } else {
+                        false
+                    }
+                }
+                SpellKind::HealAlly => {
+                    let ally = creatures
+                        .iter()
+                        .filter(|(ally, ally_pos, ally_faction, health)| {
+                            *ally != entity
+                                && *ally_faction == faction
+                                && health.current < health.max
+                                && fov.visible_tiles.contains(ally_pos)
+                                && DistanceAlg::Pythagoras.distance2d(pos, *ally_pos) <= spell.range as f32
+                                && clear_shot(map, pos, *ally_pos)
+                        })
+                        .min_by_key(|(_, _, _, health)| health.current);
+
+                    if let Some((ally, _, _, _)) = ally {
+                        if seen {
+                            log.add(LogKind::Info, format!("The {} heals {}.", name, name_of(*ally)));
+                        }
+                        heals.push((*ally, spell.power));
+                        true
+                    } else {
+                        false
+                    }
+                }
+                SpellKind::Summon(minion) => {
+                    let alive = <&Summoned>::query()
+                        .iter(ecs)
+                        .filter(|summoned| summoned.summoner == entity)
+                        .count();
+                    let spots: Vec<Point> = NEIGHBOURS
+                        .iter()
+                        .map(|delta| pos + *delta)
+                        .filter(|spot| map.can_enter_tile(*spot) && !occupied.contains(spot))
+                        .take(usize::min(spell.power as usize, MAX_SUMMONS.saturating_sub(alive)))
+                        .collect();
+
+                    // only call for help once there is a fight
+                    if enemy.is_some() && !spots.is_empty() {
+                        spots.iter().for_each(|spot| {
+                            if templates.spawn_minion(spot, minion, entity, &mut rng, depth, commands).is_some() {
+                                occupied.insert(*spot);
+                            }
+                        });
+                        if seen {
+                            log.add(LogKind::Info, format!("The {} summons help!", name));
+                        }
+                        true
+                    } else {
+                        false
+                    }
+                }
+            };
+
+            if acted {
+                spell.ready_in = spell.cooldown;
+                break;
+            }
+        }
+
+        if !acted && let Some((target, target_pos, distance)) = enemy {
+            let max_range = caster.spells.iter().map(|spell| spell.range).max().unwrap_or(0) as f32;
+            let retreat = NEIGHBOURS
+                .iter()
+                .map(|delta| pos + *delta)
+                .filter(|step| map.can_enter_tile(*step) && !occupied.contains(step))
+                .map(|step| (step, DistanceAlg::Pythagoras.distance2d(step, target_pos)))
+                .filter(|(_, step_distance)| *step_distance > distance)
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .map(|(step, _)| step);
+
+            let destination = if distance < caster.keep_distance as f32 && retreat.is_some() {
+                retreat
+            } else if distance < 1.2 {
+                commands.push(((), WantsToAttack {
+                    attacker: entity,
+                    target
+                }));
+                None
+            } else if distance > max_range || !clear_shot(map, pos, target_pos) {
+                // get into range and line of fire
+                let path = a_star_search(map.point2d_to_index(pos), map.point2d_to_index(target_pos), map);
+                path.steps
+                    .get(1)
+                    .filter(|_| path.success)
+                    .map(|step| map.index_to_point2d(*step))
+                    .filter(|step| !occupied.contains(step))
+            } else {
+                // in range and waiting on a cooldown
+                None
+            };
+
+            if let Some(destination) = destination {
+                occupied.insert(destination);
+                commands.push(((), WantsToMove {
+                    entity,
+                    destination
+                }));
+            }
+        }
+
+        commands.add_component(entity, caster);
+    });
+
+    for (target, amount) in heals {
+        if let Ok(mut target) = ecs.entry_mut(target)
+            && let Ok(health) = target.get_component_mut::<Health>()
+        {
+            health.current = i32::min(health.max, health.current + amount);
+        }
+    }
+}
+
+// nothing opaque may sit between the caster and the target
+fn clear_shot(map: &Map, from: Point, to: Point) -> bool {
+    line2d(LineAlg::Bresenham, from, to)
+        .iter()
+        .filter(|pt| **pt != from && **pt != to)
+        .all(|pt| !map.is_opaque(map.point2d_to_index(*pt)))
+}
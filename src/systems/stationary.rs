@@ -0,0 +1,41 @@
+use crate::prelude::*;
+use super::fleeing::is_fleeing;
+
+// stationary monsters never move, but will strike at an enemy next to them
+#[system]
+#[read_component(Point)]
+#[read_component(Stationary)]
+#[read_component(Player)]
+#[read_component(FleesWhenWounded)]
+#[read_component(Health)]
+#[read_component(FieldOfView)]
+#[read_component(Asleep)]
+#[read_component(Confused)]
+#[read_component(Faction)]
+pub fn stationary(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] factions: &Factions
+) {
+    let mut guards = <(Entity, &Point)>::query()
+        .filter(component::<Stationary>() & !component::<Asleep>() & !component::<Confused>());
+    let mut targets = <(Entity, &Point)>::query().filter(component::<Health>());
+    let player = <Entity>::query().filter(component::<Player>()).iter(ecs).next().unwrap();
+
+    guards
+        .iter(ecs)
+        .filter(|(entity, _)| !is_fleeing(ecs, factions, **entity, *player))
+        .for_each(|(entity, pos)| {
+            let target = targets
+                .iter(ecs)
+                .filter(|(_, target_pos)| DistanceAlg::Pythagoras.distance2d(*pos, **target_pos) < 1.2)
+                .find(|(target, _)| factions.entity_is_hostile(ecs, *entity, **target));
+
+            if let Some((target, _)) = target {
+                commands.push(((), WantsToAttack {
+                    attacker: *entity,
+                    target: *target
+                }));
+            }
+        });
+}
@@ -0,0 +1,86 @@
+use crate::prelude::*;
+
+// moves the aiming cursor and fires the ranged item once a target is chosen
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+pub fn targeting(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] targeting: &mut Targeting,
+    #[resource] camera: &Camera,
+    #[resource] log: &mut GameLog
+) {
+    let (player, player_pos, visible) = <(Entity, &Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(entity, pos, fov)| (*entity, *pos, fov.visible_tiles.clone()))
+        .next()
+        .unwrap();
+
+    let in_range = |pt: Point| {
+        visible.contains(&pt) && DistanceAlg::Pythagoras.distance2d(player_pos, pt) <= targeting.range as f32
+    };
+
+    if let Some(key) = *key {
+        match key {
+            VirtualKeyCode::Left => targeting.cursor.x -= 1,
+            VirtualKeyCode::Right => targeting.cursor.x += 1,
+            VirtualKeyCode::Up => targeting.cursor.y -= 1,
+            VirtualKeyCode::Down => targeting.cursor.y += 1,
+            VirtualKeyCode::Return | VirtualKeyCode::F => {
+                if let Some(item) = targeting.item
+                    && in_range(targeting.cursor)
+                {
+                    commands.push(((), ActivateItem {
+                        used_by: player,
+                        item,
+                        target: Some(targeting.cursor)
+                    }));
+                    targeting.item = None;
+                    *turn_state = TurnState::PlayerTurn;
+                } else {
+                    log.add(LogKind::Info, "You cannot target that spot.");
+                }
+            },
+            VirtualKeyCode::Escape => {
+                targeting.item = None;
+                *turn_state = TurnState::AwaitingInput;
+            },
+            _ => {}
+        }
+    }
+
+    // outline the tiles that will be hit, on the finer text layer
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    let offset = Point::new(camera.left_x, camera.top_y);
+    let colour = if in_range(targeting.cursor) { YELLOW } else { RED };
+    let cursor = targeting.cursor;
+    Rect::with_size(
+        cursor.x - targeting.radius,
+        cursor.y - targeting.radius,
+        targeting.radius * 2 + 1,
+        targeting.radius * 2 + 1
+    )
+        .for_each(|pt| {
+            if DistanceAlg::Pythagoras.distance2d(cursor, pt) <= targeting.radius as f32 {
+                let screen = (pt - offset) * 4;
+                let tile_colour = if pt == cursor { colour } else { ORANGE };
+                draw_batch.draw_hollow_box(
+                    Rect::with_size(screen.x, screen.y, 3, 3),
+                    ColorPair::new(tile_colour, BLACK)
+                );
+            }
+        });
+    draw_batch.print_color_centered(
+        5,
+        "Choose a target: cursor keys to aim, Enter to use, Escape to cancel.",
+        ColorPair::new(YELLOW, BLACK)
+    );
+
+    draw_batch.submit(10200).expect("Batch error");
+}
@@ -6,10 +6,15 @@ use crate::prelude::*;
 #[read_component(Health)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
+#[read_component(Asleep)]
+#[read_component(Enchantment)]
+#[read_component(Appraised)]
+#[read_component(Cursed)]
 pub fn tooltips(
     ecs: &mut SubWorld,
     #[resource] mouse_pos: &Point,
     #[resource] camera: &Camera,
+    #[resource] identification: &Identification
 ) {
     let mut positions = <(Entity, &Point, &Name)>::query();
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
@@ -26,14 +31,15 @@ pub fn tooltips(
         .filter(|(_, pos, _)| **pos == map_pos && player_fov.visible_tiles.contains(pos))
         .for_each(|(entity, _, name)| {
             let screen_pos = *mouse_pos * 4;
-            let display = if let Ok(health) = ecs.entry_ref(*entity)
-                .unwrap()
-                .get_component::<Health>()
-            {
+            let entry = ecs.entry_ref(*entity).unwrap();
+            let mut display = if let Ok(health) = entry.get_component::<Health>() {
                 format!("{} : {} hp", &name.0, health.current)
             } else {
-                name.0.clone()
+                item_name(ecs, *entity, identification)
             };
+            if entry.get_component::<Asleep>().is_ok() {
+                display.push_str(" (sleeping)");
+            }
             draw_batch.print(screen_pos, &display);
         });
     draw_batch.submit(10100).expect("Batch error");
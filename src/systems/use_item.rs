@@ -2,38 +2,96 @@ use crate::prelude::*;
 
 #[system]
 #[read_component(ActivateItem)]
-#[read_component(ProvidesHealing)]
 #[write_component(Health)]
-#[read_component(ProvidesDungeonMap)]
+#[read_component(Name)]
+#[read_component(AmuletOfYala)]
+#[read_component(Quantity)]
+#[read_component(Provides)]
+#[read_component(Carried)]
+#[read_component(Charges)]
+#[read_component(Point)]
+#[read_component(Enemy)]
+#[read_component(Item)]
+#[read_component(Hunger)]
+#[read_component(Enchantment)]
+#[read_component(Appraised)]
+#[read_component(Cursed)]
+#[read_component(Equippable)]
+#[read_component(Faction)]
+#[read_component(Boss)]
+#[read_component(Shopkeeper)]
 pub fn use_items(
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
-    #[resource] map: &mut Map
+    #[resource] map: &mut Map,
+    #[resource] log: &mut GameLog,
+    #[resource] identification: &mut Identification
 ) {
     let mut healing_to_apply = Vec::<(Entity, i32)>::new();
+    let mut rng = RandomNumberGenerator::new();
 
     <(Entity, &ActivateItem)>::query()
         .iter(ecs)
         .for_each(|(entity, activate)| {
             let item = ecs.entry_ref(activate.item);
+            if let Ok(item) = &item
+                && item.get_component::<AmuletOfYala>().is_ok()
+            {
+                // the amulet is kept, not used up
+                log.add(LogKind::Item, "The amulet hums with power, but it is not time to wear it yet.");
+                commands.remove(*entity);
+                return;
+            }
+            if let Ok(item) = &item
+                && let Ok(charges) = item.get_component::<Charges>()
+                && charges.0 < 1
+            {
+                log.add(LogKind::Item, "Nothing happens, it has no charges left.");
+                commands.remove(*entity);
+                return;
+            }
             if let Ok(item) = item {
-                if let Ok(healing) = item.get_component::<ProvidesHealing>() {
-                    healing_to_apply.push((activate.used_by, healing.amount));
+                if let Ok(name) = item.get_component::<Name>() {
+                    log.add(LogKind::Item, format!("You use the {}.", identification.name_of(&name.0)));
+                    // using an unknown item teaches the player what it was
+                    if identification.identify(&name.0) {
+                        log.add(LogKind::Item, format!("It was a {}.", name.0));
+                    }
                 }
 
-                if let Ok(_mapper) = item.get_component::<ProvidesDungeonMap>() {
-                    map.revealed_tiles.iter_mut().for_each(|t| *t = true);
+                if let Ok(provides) = item.get_component::<Provides>() {
+                    let mut effect_ctx = EffectContext {
+                        user: activate.used_by,
+                        target: activate.target,
+                        commands,
+                        map,
+                        log,
+                        identification,
+                        rng: &mut rng,
+                        healing: &mut healing_to_apply
+                    };
+                    provides.0.iter().for_each(|effect| effect.apply(ecs, &mut effect_ctx));
                 }
             }
 
-            commands.remove(activate.item);
+            // wands lose a charge, a stack only loses one of its items
+            let entry = ecs.entry_ref(activate.item);
+            let charges = entry.as_ref().ok().and_then(|e| e.get_component::<Charges>().ok().map(|c| c.0));
+            let quantity = entry.as_ref().ok().and_then(|e| e.get_component::<Quantity>().ok().map(|q| q.0));
+            match (charges, quantity) {
+                (Some(charges), _) => commands.add_component(activate.item, Charges(charges - 1)),
+                (_, Some(quantity)) if quantity > 1 => commands.add_component(activate.item, Quantity(quantity - 1)),
+                _ => commands.remove(activate.item)
+            }
             commands.remove(*entity);
         });
 
     for heal in healing_to_apply.iter() {
         if let Ok(mut target) = ecs.entry_mut(heal.0) {
             if let Ok(health) = target.get_component_mut::<Health>() {
-                health.current = i32::min(health.max, health.current + heal.1);
+                let healed = i32::min(health.max, health.current + heal.1) - health.current;
+                health.current += healed;
+                log.add(LogKind::Item, format!("You recover {} health.", healed));
             }
         }
     }
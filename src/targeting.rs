@@ -0,0 +1,52 @@
+use crate::prelude::*;
+
+// the ranged item being aimed while in the targeting state
+#[derive(Clone, Copy, Debug)]
+pub struct Targeting {
+    pub item: Option<Entity>,
+    pub cursor: Point,
+    pub range: i32,
+    // area shown around the cursor, for items such as fireballs
+    pub radius: i32
+}
+
+impl Default for Targeting {
+    fn default() -> Self {
+        Self {
+            item: None,
+            cursor: Point::zero(),
+            range: 0,
+            radius: 0
+        }
+    }
+}
+
+impl Targeting {
+    // aims the item at the nearest visible enemy, or the user when there is none
+    pub fn begin<W: EntityStore>(&mut self, ecs: &W, user: Entity, item: Entity) {
+        let entry = ecs.entry_ref(item).unwrap();
+        self.item = Some(item);
+        self.range = entry.get_component::<Ranged>().map(|r| r.0).unwrap_or(0);
+        self.radius = entry
+            .get_component::<Provides>()
+            .map(|provides| provides.0.iter().map(|effect| effect.radius()).max().unwrap_or(0))
+            .unwrap_or(0);
+
+        let user_entry = ecs.entry_ref(user).unwrap();
+        let user_pos = *user_entry.get_component::<Point>().unwrap();
+        let visible = user_entry
+            .get_component::<FieldOfView>()
+            .map(|fov| fov.visible_tiles.clone())
+            .unwrap_or_default();
+
+        self.cursor = <&Point>::query()
+            .filter(component::<Enemy>())
+            .iter(ecs)
+            .filter(|pos| visible.contains(pos))
+            .map(|pos| (*pos, DistanceAlg::Pythagoras.distance2d(user_pos, *pos)))
+            .filter(|(_, distance)| *distance <= self.range as f32)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(pos, _)| pos)
+            .unwrap_or(user_pos);
+    }
+}
@@ -5,5 +5,9 @@ pub enum TurnState {
     MonsterTurn,
     GameOver,
     Victory,
-    NextLevel
+    NextLevel,
+    ShowingLog,
+    ShowingInventory,
+    Shopping,
+    Targeting
 }